@@ -76,21 +76,51 @@
 //! This looks pretty heavyweight, but this intentional - this library is meant for large
 //! and heavyweight applications!
 //!
+//! # Panic strategy
+//!
+//! This crate is intended for applications built with the default `panic = "unwind"` strategy.
+//! With `panic = "abort"`, the panic hook still runs and so the panic is still logged, but the
+//! process then aborts immediately - [run_and_handle_panics] never gets the chance to return
+//! `None` and no graceful exit is possible. A warning is logged the first time a scope is
+//! started in this case.
+//!
 //! # Features
 //! * `use-stderr`: log panics to stderr
 //! * `use-log`: log panics with the `log` crate
 //! * `use-slog`: log panics with the `slog` crate (configured in [Builder])
 //! * `use-parking-lot`: use `parking_lot::Mutex` instead of `std::sync::Mutex`
+//! * `use-tokio`: stream panics asynchronously (see [panic_stream]) and cancel a
+//!   `CancellationToken` on the first panic
+//! * `use-metrics`: emit panic counters and backtrace resolution gauges through the `metrics` crate
+//! * `use-regex`: suppress panics by regular expression (see [Builder::suppress_messages])
+//! * `use-serde`: render crash reports as JSON (see `render_report_json`)
+//! * `use-syslog`: also log panics to syslog (Unix only)
+//! * `use-eventlog`: also log panics to the Windows Event Log (Windows only)
+//! * `use-ctrlc`: treat Ctrl-C as a fatal panic for a graceful shutdown (see
+//!   `Builder::treat_sigint_as_fatal`)
+//! * `test-util`: inject fake panics to test code that consumes panik (see `inject_panic`)
+//!
+//! If none of the logging features are enabled, panic reports are still printed to stderr unless
+//! disabled with [Builder::default_stderr_when_no_feature].
 
-use backtrace::Backtrace;
+use backtrace::{Backtrace, BacktraceFrame, BacktraceSymbol};
 
+use std::any::Any;
 use std::borrow::Cow;
-use std::fmt::Debug;
-use std::panic::{PanicInfo, UnwindSafe};
+use std::cell::Cell;
+use std::fmt::{Debug, Display, Formatter};
+use std::panic::{AssertUnwindSafe, PanicHookInfo, RefUnwindSafe, UnwindSafe};
+use std::sync::{mpsc, Arc, PoisonError};
 use std::thread::ThreadId;
+use std::time::{Duration, Instant};
 
 use std::cmp::Ordering;
-use std::ops::DerefMut;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryInto;
+use std::io::Write;
+use std::ops::{ControlFlow, DerefMut};
+use std::path::{Path, PathBuf};
 
 #[cfg(feature = "use-parking-lot")]
 use parking_lot::Mutex;
@@ -99,61 +129,571 @@ use parking_lot::Mutex;
 use std::sync::Mutex;
 
 const DEFAULT_BACKTRACE_RESOLUTION_LIMIT: usize = 8;
+const STRICT_EXIT_CODE: i32 = 1;
+#[cfg(feature = "use-ctrlc")]
+const SIGINT_EXIT_CODE: i32 = 130;
+const DEFAULT_SOFT_PANIC_THRESHOLD: usize = 1;
+const DEFAULT_SCOPE_HISTORY_LEN: usize = 16;
+#[cfg(feature = "use-tokio")]
+const PANIC_STREAM_CAPACITY: usize = 64;
+const GRACE_PERIOD_POLL_INTERVAL: Duration = Duration::from_millis(5);
+/// Identifies a [Builder::persistent_ring] file and its format version.
+const RING_MAGIC: &[u8; 8] = b"PANIKRB1";
+const RING_HEADER_SIZE: usize = 64;
+/// The size of each [Builder::persistent_ring] record, including its header. Longer messages are
+/// truncated to fit.
+const RING_RECORD_SIZE: usize = 512;
+const RING_RECORD_HEADER_SIZE: usize = 16;
+/// Even the panic machinery alone has more frames than this.
+const FFI_SUSPICIOUS_FRAME_COUNT: usize = 5;
 
 lazy_static::lazy_static! {
     static ref STATE: Mutex<State> = Mutex::new(State::default());
 }
 
+/// Mirrors the result of [has_panicked] so it can be read without locking, see
+/// [State::update_has_panicked].
+static HAS_PANICKED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// The last epoch assigned to a captured panic, see [Panic::epoch].
+static PANIC_EPOCH: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Whether drain mode has been entered, see [enter_drain_mode].
+static DRAINING: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+thread_local! {
+    static SENTINEL_CAUGHT: Cell<bool> = const { Cell::new(false) };
+    /// Set while reporting panics at the end of a scope, so a panic from a log sink isn't
+    /// registered as another panic, nor tries to take the lock again from the hook.
+    static REPORTING: Cell<bool> = const { Cell::new(false) };
+}
+
+macro_rules! log_info {
+($state:expr, $($arg:tt)+) => {
+        if !$state.quiet {
+            #[cfg(feature = "use-slog")]
+            slog::info!(&$state.slogger, $($arg)+);
+            #[cfg(feature = "use-log")]
+            log::info!($($arg)+);
+            #[cfg(feature = "use-stderr")]
+            eprintln!($($arg)+);
+            #[cfg(not(any(feature = "use-slog", feature = "use-log", feature = "use-stderr")))]
+            let _ = format_args!($($arg)+);
+        }
+    }
+}
+
+macro_rules! log_debug {
+($state:expr, $($arg:tt)+) => {
+        if !$state.quiet {
+            #[cfg(feature = "use-slog")]
+            slog::debug!(&$state.slogger, $($arg)+);
+            #[cfg(feature = "use-log")]
+            log::debug!($($arg)+);
+            #[cfg(feature = "use-stderr")]
+            eprintln!($($arg)+);
+            #[cfg(not(any(feature = "use-slog", feature = "use-log", feature = "use-stderr")))]
+            let _ = format_args!($($arg)+);
+        }
+    }
+}
+
 macro_rules! log_warn {
 ($state:expr, $($arg:tt)+) => {
-        #[cfg(feature = "use-slog")]
-        slog::warn!(&$state.slogger, $($arg)+);
-        #[cfg(feature = "use-log")]
-        log::warn!($($arg)+);
-        #[cfg(feature = "use-stderr")]
-        eprintln!($($arg)+);
+        if !$state.quiet {
+            #[cfg(feature = "use-slog")]
+            slog::warn!(&$state.slogger, $($arg)+);
+            #[cfg(feature = "use-log")]
+            log::warn!($($arg)+);
+            #[cfg(feature = "use-stderr")]
+            eprintln!($($arg)+);
+            #[cfg(not(any(feature = "use-slog", feature = "use-log", feature = "use-stderr")))]
+            let _ = format_args!($($arg)+);
+        }
     }
 }
 
 macro_rules! log_error {
 ($state:expr, $($arg:tt)+) => {
-        #[cfg(feature = "use-slog")]
-        slog::error!(&$state.slogger, $($arg)+);
-        #[cfg(feature = "use-log")]
-        log::error!($($arg)+);
-        #[cfg(feature = "use-stderr")]
-        eprintln!($($arg)+);
+        if !$state.quiet {
+            #[cfg(feature = "use-slog")]
+            slog::error!(&$state.slogger, $($arg)+);
+            #[cfg(feature = "use-log")]
+            log::error!($($arg)+);
+            #[cfg(feature = "use-stderr")]
+            eprintln!($($arg)+);
+            #[cfg(any(all(feature = "use-syslog", unix), all(feature = "use-eventlog", windows)))]
+            os_log(OsLogLevel::Error, &format!($($arg)+));
+            #[cfg(not(any(feature = "use-slog", feature = "use-log", feature = "use-stderr")))]
+            let _ = format_args!($($arg)+);
+        }
     }
 }
 
 macro_rules! log_crit {
 ($state:expr, $($arg:tt)+) => {
-        #[cfg(feature = "use-slog")]
-        slog::crit!(&$state.slogger, $($arg)+);
-        #[cfg(feature = "use-log")]
-        log::error!($($arg)+);
-        #[cfg(feature = "use-stderr")]
-        eprintln!($($arg)+);
+        if !$state.quiet {
+            #[cfg(feature = "use-slog")]
+            slog::crit!(&$state.slogger, $($arg)+);
+            #[cfg(feature = "use-log")]
+            log::error!($($arg)+);
+            #[cfg(feature = "use-stderr")]
+            eprintln!($($arg)+);
+            #[cfg(any(all(feature = "use-syslog", unix), all(feature = "use-eventlog", windows)))]
+            os_log(OsLogLevel::Crit, &format!($($arg)+));
+            #[cfg(not(any(feature = "use-slog", feature = "use-log", feature = "use-stderr")))]
+            if $state.stderr_fallback {
+                eprintln!($($arg)+);
+            }
+        }
     }
 }
 
+/// The logging configuration copied out of [State], to log without holding the lock.
+struct LogTarget {
+    quiet: bool,
+    #[cfg(not(any(feature = "use-slog", feature = "use-log", feature = "use-stderr")))]
+    stderr_fallback: bool,
+    #[cfg(feature = "use-slog")]
+    slogger: slog::Logger,
+}
+
+/// A message formatted while holding the lock, to log once it's released as logging may block.
+enum DeferredLog {
+    Info(String),
+    Warn(String),
+    Error(String),
+    Crit(String),
+}
+
+/// The settings used to describe a panic on a given thread, copied out of [State] to build a
+/// [Panic] without holding the lock.
+struct PanicDescriber {
+    thread_label: Option<String>,
+    thread_id_formatter: Option<ThreadIdFormatter>,
+    unknown_payload_message: Option<UnknownPayloadMessage>,
+}
+
 struct State {
     panics: Vec<Panic>,
     backtrace_resolution_limit: usize,
+    backtrace_resolution_time_budget: Option<Duration>,
+    preserve_panics: bool,
+    grace_period: Duration,
+    max_scope_duration: Option<Duration>,
+    log_rate_limiter: Option<LogRateLimiter>,
+    soft_panic_threshold: usize,
+    retain_payload: bool,
+    thread_labels: HashMap<ThreadId, String>,
+    registries: HashMap<ThreadId, Registry>,
+    /// Registries that had a thread registered under them when it exited, kept for
+    /// [merge_all_registries] without keeping them alive.
+    exited_registries: Vec<std::sync::Weak<RegistryInner>>,
+    breadcrumb_fn: Option<BreadcrumbFn>,
+    scope_history_len: usize,
+    build_info: Option<BuildInfo>,
+    env_snapshot: Vec<(String, Option<String>)>,
+    backtrace_format: BacktraceFormat,
+    verbosity: Verbosity,
+    quiet: bool,
+    stderr_fallback: bool,
+    resolver_tx: Option<mpsc::Sender<u64>>,
+    first_panic_fn: Option<FirstPanicFn>,
+    panic_log_file: Option<PathBuf>,
+    #[cfg(target_os = "linux")]
+    persistent_ring: Option<PersistentRing>,
+    clock: Option<Clock>,
+    backtrace_capturer: Option<BacktraceCapturer>,
+    thread_id_formatter: Option<ThreadIdFormatter>,
+    unknown_payload_message: Option<UnknownPayloadMessage>,
+    suppressions: Vec<Suppression>,
+    max_message_len: Option<usize>,
+    max_total_backtrace_bytes: Option<usize>,
+    /// The estimated size of the stored backtraces, only tracked with
+    /// [Builder::max_total_backtrace_bytes].
+    total_backtrace_bytes: usize,
+    /// The index of the oldest panic whose backtrace may still be evicted.
+    eviction_cursor: usize,
+    backtraces_evicted: usize,
+    /// Whether a panic has been stored in this scope, see [Builder::capture_first_only].
+    first_panic_captured: bool,
+    panics_dropped: usize,
+    scope_name: Option<&'static str>,
+    /// Whether the last scope's panics must be read before the next scope, see
+    /// [Builder::require_handled].
+    require_handled: bool,
+    drain_on_first_panic: bool,
+    /// Panics carried over from previous scopes, see [Builder::preserve_panics_across_scopes].
+    preexisting_panics: usize,
+    panics_consumed: bool,
+    panic_budget: Option<(usize, Duration)>,
+    capture_backtraces: bool,
+    capture_cpu_time: bool,
+    capture_live_thread_count: bool,
+    capture_first_only: bool,
+    capture_after_scope: bool,
+    #[cfg(target_os = "linux")]
+    catch_stack_overflow: bool,
+    #[cfg(feature = "use-ctrlc")]
+    treat_sigint_as_fatal: bool,
+    abort_on_main_thread_panic: Option<i32>,
+    exit_fn: Option<ExitFn>,
+    /// Panics that occurred after a scope ended, see [Builder::capture_after_scope].
+    post_scope_panics: Vec<Panic>,
+    #[cfg(feature = "use-log")]
+    capture_error_logs: bool,
+    /// Error logs recorded as panics, see [Builder::capture_error_logs].
+    #[cfg(feature = "use-log")]
+    soft_panics: Vec<Panic>,
+    budget_timestamps: VecDeque<Instant>,
+    budget_exceeded: bool,
     is_running: bool,
+    scope_owner: Option<ThreadId>,
+    /// The owner of the current or most recent scope, unlike `scope_owner` not cleared when it
+    /// ends.
+    last_scope_owner: Option<ThreadId>,
+    scope_started: Option<Instant>,
+    /// See [pause_capture].
+    capture_paused: bool,
+    /// Not cleared between scopes, as pooled threads are registered once, but removed when the
+    /// thread exits.
+    spawn_sites: HashMap<ThreadId, Backtrace>,
+    last_scope: Option<ScopeSummary>,
+    last_attempt_count: usize,
+    next_sequence: u64,
+    scope_history: VecDeque<ScopeStat>,
+    capture_healthy: bool,
+    /// The address of the hook installed by [Builder::capture_after_scope] and the hook it wraps,
+    /// so the next scope restores the wrapped hook rather than chaining onto it.
+    post_scope_hook: Option<(usize, Arc<PanicHook>)>,
 
     #[cfg(feature = "use-slog")]
     slogger: slog::Logger,
+    #[cfg(feature = "use-slog")]
+    reset_slogger: bool,
+
+    #[cfg(feature = "use-tokio")]
+    panic_sender: tokio::sync::broadcast::Sender<Panic>,
+    #[cfg(feature = "use-tokio")]
+    cancellation_token: Option<tokio_util::sync::CancellationToken>,
+    panic_semaphore: Option<PanicSemaphore>,
 }
 
 /// Describes a panic that has occurred.
+///
+/// Panics are equal and ordered by the order they were captured in (see [Panic::sequence]), not
+/// by their contents.
 #[derive(Debug, Clone)]
 pub struct Panic {
+    sequence: u64,
+    epoch: u64,
     message: String,
     thread_id: ThreadId,
+    /// The thread ID as formatted by [Builder::thread_id_formatter].
+    thread_id_string: String,
     thread: String,
+    thread_label: String,
+    thread_description: Option<String>,
+    restart_suggested: bool,
+    message_truncated: bool,
+    raw_message: RawMessage,
+    thread_stack_size: Option<usize>,
+    thread_cpu_time: Option<Duration>,
+    live_thread_count: Option<usize>,
+    payload_type: Option<&'static str>,
     backtrace: Backtrace,
+    backtrace_captured: bool,
     backtrace_resolved: bool,
+    location: Option<PanicLocation>,
+    payload: Option<Payload>,
+    breadcrumbs: Vec<String>,
+    backtrace_format: BacktraceFormat,
+    origin_crate: Option<String>,
+    captured_by: Option<&'static str>,
+    possibly_ffi: bool,
+    spawn_backtrace: Option<Backtrace>,
+    context: HashMap<String, String>,
+}
+
+/// How backtraces are rendered, see [Builder::backtrace_format].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BacktraceFormat {
+    /// Omits frames from the standard library and runtime, similar to `RUST_BACKTRACE=1`.
+    Short,
+    /// Includes all frames, similar to `RUST_BACKTRACE=full`.
+    Full,
+}
+
+/// How much is logged about each panic when a scope ends, see [Builder::verbosity].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity {
+    /// Only the number of panics.
+    Silent,
+    /// A single line per panic, see [Panic::summary_line].
+    Summary,
+    /// The full report for each panic, including its backtrace if resolved.
+    Full,
+}
+
+/// The source location a panic occurred at.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize))]
+pub struct PanicLocation {
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+/// Identifies the build of the application, for including in crash reports. See
+/// [Builder::build_info].
+///
+/// This is provided by the application, e.g. from `env!` in a build script.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize))]
+pub struct BuildInfo {
+    /// The application version.
+    pub version: String,
+    /// The git commit the application was built from.
+    pub git_sha: String,
+    /// When the application was built.
+    pub build_timestamp: String,
+}
+
+/// An independent set of panics for a subsystem (e.g. audio or networking), so it can be
+/// monitored separately from the rest of the application.
+///
+/// Panics on threads registered with [Registry::register_thread] are stored in the registry
+/// instead of the global set used by [run_and_handle_panics], so they are neither fatal to the
+/// current scope nor returned by [panics]. Cloning a registry gives another handle to the same
+/// panics.
+///
+/// ```
+/// # fn main() {
+/// let audio = panik::Registry::new("audio");
+///
+/// let result = panik::run_and_handle_panics(|| {
+///     let audio = audio.clone();
+///     std::thread::spawn(move || {
+///         audio.register_current_thread();
+///         panic!("audio device lost");
+///     })
+///     .join()
+///     .is_err()
+/// });
+///
+/// assert_eq!(result, Some(true));
+/// assert!(audio.has_panicked());
+/// assert!(!panik::has_panicked());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Registry(Arc<RegistryInner>);
+
+#[derive(Debug)]
+struct RegistryInner {
+    name: String,
+    /// Always std's mutex so registries can be captured across `catch_unwind`.
+    panics: std::sync::Mutex<Vec<Panic>>,
+}
+
+/// A counting semaphore that is released once per panic, see [Builder::panic_semaphore].
+///
+/// Each panic adds a permit, so after several panics the same number of acquires succeed without
+/// blocking, and every blocked waiter is woken in turn. Clones share the same permits.
+#[derive(Debug, Clone, Default)]
+pub struct PanicSemaphore(Arc<PanicSemaphoreInner>);
+
+#[derive(Debug, Default)]
+struct PanicSemaphoreInner {
+    /// Always std's mutex to pair with [std::sync::Condvar].
+    permits: std::sync::Mutex<usize>,
+    released: std::sync::Condvar,
+}
+
+/// Wraps a `log` logger to record error logs as soft panics, see [Builder::capture_error_logs].
+/// All records are passed through to the inner logger.
+///
+/// ```
+/// # fn main() {
+/// # let my_logger = env_logger::Logger::from_default_env();
+/// log::set_boxed_logger(Box::new(panik::ErrorLogCapture::new(my_logger))).unwrap();
+/// log::set_max_level(log::LevelFilter::Info);
+///
+/// let result = panik::Builder::new()
+///     .capture_error_logs(true)
+///     .run_and_handle_panics(|| log::error!("disk full"));
+/// assert!(result.is_some());
+/// assert_eq!(panik::soft_panics()[0].message(), "disk full");
+/// # }
+/// ```
+#[cfg(feature = "use-log")]
+#[derive(Debug)]
+pub struct ErrorLogCapture<L>(L);
+
+/// The panics that caused a scope to fail, see [run_and_handle_panics_result].
+///
+/// This can be empty if the scope failed only because the closure itself panicked with a panic
+/// that was not stored, e.g. one suppressed by [Builder::suppress_messages].
+#[derive(Debug, Clone)]
+pub struct PanicError(Vec<Panic>);
+
+/// A cheaply cloneable copy of the stored panics taken at a point in time, see [snapshot].
+#[derive(Debug, Clone)]
+pub struct PanicSnapshot(Arc<[Panic]>);
+
+/// The components of a panic as captured, before panik formats or truncates them, see
+/// [Panic::raw].
+#[derive(Debug, Clone, Copy)]
+pub struct PanicRaw<'a> {
+    message: Option<&'a str>,
+    location: Option<(&'a str, u32, u32)>,
+    thread_id: ThreadId,
+}
+
+/// The message carried by a panic's payload, stored separately only if it differs from the
+/// formatted message to avoid a copy in the common case.
+#[derive(Debug, Clone)]
+enum RawMessage {
+    /// The payload wasn't a string.
+    NotString,
+    SameAsMessage,
+    /// The message was truncated to this length before the suffix was appended.
+    TruncatedMessage(usize),
+    Different(String),
+}
+
+/// Identifies panics caused by the same bug, see [Panic::key].
+///
+/// This is made of the message and full source location (file, line and column), but not the
+/// thread, so the same panic hit by several threads has the same key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize))]
+pub struct PanicKey {
+    message: String,
+    location: Option<PanicLocation>,
+}
+
+/// A panic and the number of panics with the same [PanicKey], see [deduplicated_panics].
+#[derive(Debug, Clone)]
+pub struct DeduplicatedPanic {
+    panic: Panic,
+    count: usize,
+}
+
+/// A panic read from another process's panic log, see [read_panics_from_file].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "use-serde", derive(serde::Serialize))]
+pub struct PanicLite {
+    process_id: u32,
+    sequence: u64,
+    thread: String,
+    message: String,
+    location: Option<PanicLocation>,
+    build_info: Option<BuildInfo>,
+    env: Vec<(String, Option<String>)>,
+}
+
+/// Formats a panic for logging and [render_report].
+struct PanicReport<'a>(&'a Panic);
+
+/// Captures breadcrumbs at the time of a panic, see [Builder::breadcrumb_fn].
+type BreadcrumbFn = Arc<dyn Fn() -> Vec<String> + Send + Sync>;
+
+/// Called with all panics when a strict scope fails, see [Builder::failure_action].
+type FailureAction = Arc<dyn Fn(&[Panic]) + Send + Sync>;
+
+/// A known-benign panic message to ignore, see [Builder::suppress_messages].
+#[derive(Clone)]
+enum Suppression {
+    Substring(String),
+    #[cfg(feature = "use-regex")]
+    Regex(regex::Regex),
+}
+
+/// Supplies the current time, see [Builder::clock].
+type Clock = Arc<dyn Fn() -> Instant + Send + Sync>;
+
+/// Captures the backtrace of a panic, see [Builder::backtrace_capturer].
+type BacktraceCapturer = Arc<dyn Fn() -> Backtrace + Send + Sync>;
+
+/// Called once with the first panic in a scope, see [Builder::on_first_panic].
+type FirstPanicFn = Arc<std::sync::Mutex<Option<Box<dyn FnOnce(&Panic) + Send>>>>;
+
+/// Chooses the process exit code when a strict scope fails, see
+/// [Builder::exit_code_classifier].
+type ExitCodeClassifier = Arc<dyn Fn(&[Panic]) -> i32 + Send + Sync>;
+
+/// Exits the process with the given code, see [Builder::exit_fn].
+type ExitFn = Arc<dyn Fn(i32) + Send + Sync>;
+
+/// Formats thread IDs in panics and logs, see [Builder::thread_id_formatter].
+type ThreadIdFormatter = Arc<dyn Fn(ThreadId) -> String + Send + Sync>;
+
+/// Describes a panic whose payload type isn't recognised, see [Builder::unknown_payload_message].
+type UnknownPayloadMessage = Arc<dyn Fn(&PanicHookInfo) -> String + Send + Sync>;
+
+/// Describes the thread a panic occurred on at report time, see [Builder::thread_describer].
+type ThreadDescriber = Arc<dyn Fn(ThreadId) -> Option<String> + Send + Sync>;
+
+/// Decides whether a panic's subsystem should be restarted, see [Builder::restart_classifier].
+type RestartClassifier = Arc<dyn Fn(&Panic) -> bool + Send + Sync>;
+
+/// The original payload of a panic, shared between clones of a [Panic].
+type Payload = Arc<std::sync::Mutex<Box<dyn Any + Send>>>;
+
+/// A panic hook as taken from and given to std.
+type PanicHook = Box<dyn Fn(&PanicHookInfo) + Send + Sync>;
+
+/// Throttles logging of panics as they occur, see [Builder::log_rate_limit].
+struct LogRateLimiter {
+    max_per_sec: u32,
+    window_start: Instant,
+    logged: u32,
+    suppressed: usize,
+}
+
+/// Payload of the panic raised to check the panic hook is installed.
+struct HookSentinel;
+
+/// Summary of a completed call to [run_and_handle_panics], see [last_scope_summary].
+#[derive(Debug, Clone)]
+pub struct ScopeSummary {
+    panic_count: usize,
+    severity: Severity,
+}
+
+/// Statistics about a completed call to [run_and_handle_panics], see [recent_scope_stats].
+#[derive(Debug, Clone)]
+pub struct ScopeStat {
+    panic_count: usize,
+    resolved_count: usize,
+    duration: Duration,
+}
+
+/// How severe the panics that occurred in a scope were.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// No panics occurred.
+    Clean,
+    /// Fewer panics than [Builder::soft_panic_threshold] occurred on other threads, and the
+    /// closure's result was returned.
+    Soft,
+    /// The closure panicked, or the soft threshold was reached.
+    Fatal,
+}
+
+/// What to do when a scope is started while another is already running, see
+/// [Builder::on_nested].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NestedPolicy {
+    /// Panic, which is then captured by the outer scope.
+    #[default]
+    Panic,
+    /// Return None without running the closure.
+    ReturnNone,
+    /// Run the closure as part of the outer scope, without starting a new one. Any panics are
+    /// handled by the outer scope.
+    Transparent,
 }
 
 /// Builder for panic handling configuration.
@@ -161,31 +701,208 @@ pub struct Panic {
 pub struct Builder {
     #[cfg(feature = "use-slog")]
     slogger: Option<slog::Logger>,
+    #[cfg(feature = "use-slog")]
+    reset_slogger: bool,
+
+    #[cfg(feature = "use-tokio")]
+    cancellation_token: Option<tokio_util::sync::CancellationToken>,
 
+    panic_semaphore: Option<PanicSemaphore>,
     backtrace_resolution_limit: usize,
+    backtrace_resolution_time_budget: Option<Duration>,
+    preserve_panics: bool,
+    grace_period: Duration,
+    max_scope_duration: Option<Duration>,
+    log_rate_limit: Option<u32>,
+    soft_panic_threshold: usize,
+    retain_payload: bool,
+    breadcrumb_fn: Option<BreadcrumbFn>,
+    failure_action: Option<FailureAction>,
+    exit_code_classifier: Option<ExitCodeClassifier>,
+    abort_on_main_thread_panic: Option<i32>,
+    exit_fn: Option<ExitFn>,
+    first_panic_fn: Option<FirstPanicFn>,
+    panic_log_file: Option<PathBuf>,
+    #[cfg(target_os = "linux")]
+    persistent_ring: Option<(PathBuf, usize)>,
+    clock: Option<Clock>,
+    backtrace_capturer: Option<BacktraceCapturer>,
+    thread_id_formatter: Option<ThreadIdFormatter>,
+    unknown_payload_message: Option<UnknownPayloadMessage>,
+    suppressions: Vec<Suppression>,
+    suppressions_env_var: Option<String>,
+    max_message_len: Option<usize>,
+    max_total_backtrace_bytes: Option<usize>,
+    scope_name: Option<&'static str>,
+    require_handled: bool,
+    drain_on_first_panic: bool,
+    log_clean_exit: bool,
+    thread_describer: Option<ThreadDescriber>,
+    restart_classifier: Option<RestartClassifier>,
+    scope_history_len: usize,
+    build_info: Option<BuildInfo>,
+    env_vars: Vec<String>,
+    backtrace_format: Option<BacktraceFormat>,
+    verbosity: Verbosity,
+    max_attempts: usize,
+    nested_policy: NestedPolicy,
+    quiet: bool,
+    stderr_fallback: bool,
+    background_resolver: bool,
+    panic_budget: Option<(usize, Duration)>,
+    capture_backtraces: Option<bool>,
+    capture_cpu_time: bool,
+    capture_live_thread_count: bool,
+    capture_first_only: bool,
+    capture_after_scope: bool,
+    #[cfg(target_os = "linux")]
+    catch_stack_overflow: bool,
+    #[cfg(feature = "use-ctrlc")]
+    treat_sigint_as_fatal: bool,
+    #[cfg(feature = "use-log")]
+    capture_error_logs: bool,
+    backtrace_env_control: bool,
 }
 
-struct GlobalStateGuard;
+struct GlobalStateGuard {
+    /// Panics carried over from previous scopes, see [Builder::preserve_panics_across_scopes].
+    preexisting_panics: usize,
+    /// The thread that started the scope, whose panics are resolved first.
+    thread: ThreadId,
+    started: Instant,
+    /// Dropping the sender stops the watchdog, see [Builder::max_scope_duration].
+    watchdog: Option<(mpsc::Sender<()>, std::thread::JoinHandle<()>)>,
+    /// Identifies our hook, to tell if it was replaced during the scope, see [hook_address].
+    hook_address: usize,
+    /// The hook installed before the scope, restored when it ends.
+    previous_hook: Option<Arc<PanicHook>>,
+}
 
 impl Builder {
     pub fn new() -> Self {
         Builder {
             #[cfg(feature = "use-slog")]
             slogger: None,
+            #[cfg(feature = "use-slog")]
+            reset_slogger: false,
 
+            #[cfg(feature = "use-tokio")]
+            cancellation_token: None,
+
+            panic_semaphore: None,
             backtrace_resolution_limit: DEFAULT_BACKTRACE_RESOLUTION_LIMIT,
+            backtrace_resolution_time_budget: None,
+            preserve_panics: false,
+            grace_period: Duration::ZERO,
+            max_scope_duration: None,
+            log_rate_limit: None,
+            soft_panic_threshold: DEFAULT_SOFT_PANIC_THRESHOLD,
+            retain_payload: false,
+            breadcrumb_fn: None,
+            failure_action: None,
+            exit_code_classifier: None,
+            abort_on_main_thread_panic: None,
+            exit_fn: None,
+            first_panic_fn: None,
+            panic_log_file: None,
+            #[cfg(target_os = "linux")]
+            persistent_ring: None,
+            clock: None,
+            backtrace_capturer: None,
+            thread_id_formatter: None,
+            unknown_payload_message: None,
+            suppressions: Vec::new(),
+            suppressions_env_var: None,
+            max_message_len: None,
+            max_total_backtrace_bytes: None,
+            scope_name: None,
+            require_handled: false,
+            drain_on_first_panic: false,
+            log_clean_exit: false,
+            thread_describer: None,
+            restart_classifier: None,
+            scope_history_len: DEFAULT_SCOPE_HISTORY_LEN,
+            build_info: None,
+            env_vars: Vec::new(),
+            backtrace_format: None,
+            verbosity: Verbosity::Full,
+            max_attempts: 1,
+            nested_policy: NestedPolicy::Panic,
+            quiet: false,
+            stderr_fallback: true,
+            background_resolver: false,
+            panic_budget: None,
+            capture_backtraces: None,
+            capture_cpu_time: false,
+            capture_live_thread_count: false,
+            capture_first_only: false,
+            capture_after_scope: false,
+            #[cfg(target_os = "linux")]
+            catch_stack_overflow: false,
+            #[cfg(feature = "use-ctrlc")]
+            treat_sigint_as_fatal: false,
+            #[cfg(feature = "use-log")]
+            capture_error_logs: false,
+            backtrace_env_control: true,
         }
     }
 
     #[cfg(feature = "use-slog")]
     /// Sets the slog logger to log to.
+    ///
+    /// The logger persists across scopes, including those started with the free functions such as
+    /// [run_and_handle_panics], until another is set or it is reset with
+    /// [Builder::reset_slogger_on_scope_end].
     pub fn slogger(mut self, slogger: impl Into<slog::Logger>) -> Self {
         self.slogger = Some(slogger.into());
         self
     }
 
+    #[cfg(feature = "use-slog")]
+    /// Resets the slog logger to the default `slog-stdlog` logger when the scope ends. Defaults to
+    /// false.
+    pub fn reset_slogger_on_scope_end(mut self, reset: bool) -> Self {
+        self.reset_slogger = reset;
+        self
+    }
+
+    #[cfg(feature = "use-tokio")]
+    /// Sets a token to cancel when the first panic in the scope is registered, so async tasks
+    /// awaiting [CancellationToken::cancelled](tokio_util::sync::CancellationToken::cancelled)
+    /// can wind down without polling [has_panicked]. Defaults to none.
+    ///
+    /// This only fires on the first panic, as cancelling is idempotent.
+    pub fn cancellation_token(mut self, token: tokio_util::sync::CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Sets a semaphore to release a permit on for every panic registered in the scope, so the
+    /// main loop can wait on it alongside its other event-based primitives rather than polling
+    /// [has_panicked]. Defaults to none.
+    ///
+    /// ```
+    /// # fn main() {
+    /// let semaphore = panik::PanicSemaphore::new();
+    /// let result = panik::Builder::new()
+    ///     .panic_semaphore(semaphore.clone())
+    ///     .run_and_handle_panics(|| {
+    ///         std::thread::spawn(|| panic!("oh no"));
+    ///         semaphore.acquire();
+    ///     });
+    /// assert!(result.is_none());
+    /// # }
+    /// ```
+    pub fn panic_semaphore(mut self, semaphore: PanicSemaphore) -> Self {
+        self.panic_semaphore = Some(semaphore);
+        self
+    }
+
     /// Sets the limit on backtraces to resolve. Defaults to 8.
     ///
+    /// Panics on the thread that started the scope (typically the main thread) are resolved
+    /// first, followed by the rest in the order they occurred.
+    ///
     /// Useful in the case where there are many threads panicking with the same reason, and it can
     /// take a long time to resolve them all.
     pub fn backtrace_resolution_limit(mut self, n: usize) -> Self {
@@ -193,230 +910,4382 @@ impl Builder {
         self
     }
 
-    fn apply_settings(&mut self) {
-        let mut state = state_mutex();
+    /// Sets the maximum time to spend resolving backtraces, after which the remaining
+    /// backtraces are left unresolved. Defaults to no limit.
+    ///
+    /// This is combined with [Builder::backtrace_resolution_limit], so resolution stops as soon
+    /// as either limit is reached. The elapsed time is checked before resolving each backtrace,
+    /// so the budget may be overrun by the time taken to resolve a single one.
+    pub fn backtrace_resolution_time_budget(mut self, budget: Duration) -> Self {
+        self.backtrace_resolution_time_budget = Some(budget);
+        self
+    }
+
+    /// Keeps panics from previous scopes instead of clearing them when a new scope begins.
+    /// Defaults to false.
+    ///
+    /// Useful for long-running processes that guard multiple phases and want a cumulative report
+    /// of all panics at the end.
+    pub fn preserve_panics_across_scopes(mut self, preserve: bool) -> Self {
+        self.preserve_panics = preserve;
+        self
+    }
+
+    /// Sets how long to wait for panics on other threads to be registered after the closure
+    /// returns successfully. Defaults to zero, i.e. no waiting.
+    ///
+    /// Useful when worker threads may still be running (and about to panic) when the closure
+    /// returns, so their panics aren't missed. The wait ends early as soon as a panic is
+    /// registered.
+    pub fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Records a synthetic panic if the scope is still running after the given duration, so
+    /// hangs that never panic on their own are noticed by [has_panicked] pollers. Defaults to no
+    /// limit.
+    ///
+    /// Unlike a timeout, the closure is not abandoned and keeps running until it returns. The
+    /// timer runs on a separate thread, which is stopped when the scope ends.
+    pub fn max_scope_duration(mut self, max_duration: Duration) -> Self {
+        self.max_scope_duration = Some(max_duration);
+        self
+    }
+
+    /// Limits the number of panics logged as they occur to `max_per_sec` per second, to avoid
+    /// flooding the logging backend when many threads panic at once. Defaults to unlimited.
+    ///
+    /// The number of panics that weren't logged is reported periodically instead. All panics are
+    /// still stored regardless of this limit.
+    pub fn log_rate_limit(mut self, max_per_sec: u32) -> Self {
+        self.log_rate_limit = Some(max_per_sec);
+        self
+    }
+
+    /// Sets the number of panics at which they are considered fatal. Defaults to 1, i.e. any panic
+    /// is fatal.
+    ///
+    /// If fewer panics than this occur on other threads and the closure returns successfully, the
+    /// panics are logged as warnings and the closure's result is still returned. A panic in the
+    /// closure itself is always fatal. See [last_scope_summary] for which case occurred.
+    pub fn soft_panic_threshold(mut self, threshold: usize) -> Self {
+        self.soft_panic_threshold = threshold;
+        self
+    }
+
+    /// Retains the original payload of a panic in the closure, so it can be inspected with
+    /// [Panic::downcast_payload]. Defaults to false.
+    pub fn retain_payload(mut self, retain: bool) -> Self {
+        self.retain_payload = retain;
+        self
+    }
+
+    /// Sets a function to capture a snapshot of "what was happening" breadcrumbs when a panic
+    /// occurs, e.g. the current frame and system in a game engine. They are available in
+    /// [Panic::breadcrumbs].
+    ///
+    /// This is called from the panic hook on the panicking thread, so must be cheap and must not
+    /// panic itself.
+    pub fn breadcrumb_fn(mut self, f: impl Fn() -> Vec<String> + Send + Sync + 'static) -> Self {
+        self.breadcrumb_fn = Some(Arc::new(f));
+        self
+    }
+
+    /// Sets the number of scopes to keep statistics for in [recent_scope_stats]. Defaults to 16.
+    pub fn scope_history_len(mut self, n: usize) -> Self {
+        self.scope_history_len = n;
+        self
+    }
+
+    /// Sets the build information to include in [render_report], [Builder::panic_log_file] and
+    /// [Builder::persistent_ring], so every crash report can be traced back to a specific build.
+    ///
+    /// This persists across scopes until replaced.
+    pub fn build_info(mut self, build_info: BuildInfo) -> Self {
+        self.build_info = Some(build_info);
+        self
+    }
+
+    /// Sets environment variables (e.g. feature flags) to snapshot when each scope begins, to
+    /// include in [render_report] and each line of the [Builder::panic_log_file] as reproducible
+    /// crash context. Defaults to none.
+    ///
+    /// The values are captured when the scope begins rather than when a panic occurs, so later
+    /// changes to the environment don't affect the report. Only the named variables are
+    /// captured, to avoid leaking secrets from the rest of the environment.
+    pub fn capture_env_vars(mut self, names: Vec<String>) -> Self {
+        self.env_vars = names;
+        self
+    }
+
+    /// Sets how backtraces are rendered when logged and in [Panic::backtrace_string], taking
+    /// precedence over [Builder::backtrace_env_control]. Defaults to [BacktraceFormat::Full].
+    pub fn backtrace_format(mut self, format: BacktraceFormat) -> Self {
+        self.backtrace_format = Some(format);
+        self
+    }
 
+    /// Sets how much is logged about each panic when the scope ends. Defaults to
+    /// [Verbosity::Full].
+    ///
+    /// This doesn't affect the line logged as each panic occurs, or which backtraces are resolved.
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    fn apply_settings(&self, state: &mut State) {
         #[cfg(feature = "use-slog")]
         {
-            state.slogger = self.slogger.take().unwrap_or_else(default_slogger);
+            if let Some(slogger) = &self.slogger {
+                state.slogger = slogger.clone();
+            }
+            state.reset_slogger = self.reset_slogger;
+        }
+
+        #[cfg(feature = "use-tokio")]
+        {
+            state.cancellation_token = self.cancellation_token.clone();
         }
 
+        state.panic_semaphore = self.panic_semaphore.clone();
         state.backtrace_resolution_limit = self.backtrace_resolution_limit;
+        state.backtrace_resolution_time_budget = self.backtrace_resolution_time_budget;
+        state.preserve_panics = self.preserve_panics;
+        state.grace_period = self.grace_period;
+        state.max_scope_duration = self.max_scope_duration;
+        state.clock = self.clock.clone();
+        state.backtrace_capturer = self.backtrace_capturer.clone();
+        state.thread_id_formatter = self.thread_id_formatter.clone();
+        state.unknown_payload_message = self.unknown_payload_message.clone();
+        let now = state.now();
+        state.log_rate_limiter = self
+            .log_rate_limit
+            .map(|max_per_sec| LogRateLimiter::new(max_per_sec, now));
+        state.soft_panic_threshold = self.soft_panic_threshold;
+        state.retain_payload = self.retain_payload;
+        state.breadcrumb_fn = self.breadcrumb_fn.clone();
+        state.scope_history_len = self.scope_history_len;
+        if let Some(build_info) = &self.build_info {
+            state.build_info = Some(build_info.clone());
+        }
+        state.env_snapshot = self
+            .env_vars
+            .iter()
+            .map(|name| (name.clone(), std::env::var(name).ok()))
+            .collect();
+        state.verbosity = self.verbosity;
+        state.quiet = self.quiet;
+        state.stderr_fallback = self.stderr_fallback;
+        state.panic_budget = self.panic_budget;
+        state.first_panic_fn = self.first_panic_fn.clone();
+        state.panic_log_file = self.panic_log_file.clone();
+        #[cfg(target_os = "linux")]
+        if let Some((path, capacity)) = &self.persistent_ring {
+            match PersistentRing::open(path, *capacity) {
+                Ok(ring) => state.persistent_ring = Some(ring),
+                Err(err) => log_warn!(
+                    state,
+                    "failed to open panic ring buffer {}: {}",
+                    path.display(),
+                    err
+                ),
+            }
+        }
+
+        // same precedence as std's library backtraces
+        let env = if self.backtrace_env_control {
+            std::env::var("RUST_LIB_BACKTRACE")
+                .or_else(|_| std::env::var("RUST_BACKTRACE"))
+                .ok()
+        } else {
+            None
+        };
+
+        state.backtrace_format = match (self.backtrace_format, env.as_deref()) {
+            (Some(format), _) => format,
+            (None, Some("full")) => BacktraceFormat::Full,
+            (None, Some(val)) if val != "0" => BacktraceFormat::Short,
+            (None, _) => BacktraceFormat::Full,
+        };
+
+        state.capture_after_scope = self.capture_after_scope;
+        state.abort_on_main_thread_panic = self.abort_on_main_thread_panic;
+        state.exit_fn = self.exit_fn.clone();
+        #[cfg(target_os = "linux")]
+        {
+            state.catch_stack_overflow = self.catch_stack_overflow;
+            if self.catch_stack_overflow {
+                install_stack_overflow_handler(state);
+            }
+        }
+        #[cfg(feature = "use-ctrlc")]
+        {
+            state.treat_sigint_as_fatal = self.treat_sigint_as_fatal;
+            if self.treat_sigint_as_fatal {
+                install_sigint_handler(state);
+            }
+        }
+        #[cfg(feature = "use-log")]
+        {
+            state.capture_error_logs = self.capture_error_logs;
+        }
+
+        state.max_message_len = self.max_message_len;
+        state.max_total_backtrace_bytes = self.max_total_backtrace_bytes;
+        state.scope_name = self.scope_name;
+        state.require_handled = self.require_handled;
+        state.drain_on_first_panic = self.drain_on_first_panic;
+        state.suppressions = self.suppressions.clone();
+        if let Some(path) = self
+            .suppressions_env_var
+            .as_ref()
+            .and_then(std::env::var_os)
+        {
+            match std::fs::read_to_string(&path) {
+                Ok(contents) => state.suppressions.extend(
+                    contents
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty())
+                        .map(|line| Suppression::Substring(line.to_owned())),
+                ),
+                Err(err) => log_warn!(
+                    state,
+                    "failed to read suppressed panic messages from {}: {}",
+                    Path::new(&path).display(),
+                    err
+                ),
+            }
+        }
+        state.capture_cpu_time = self.capture_cpu_time;
+        state.capture_live_thread_count = self.capture_live_thread_count;
+        state.capture_first_only = self.capture_first_only;
+        state.capture_backtraces = match self.capture_backtraces {
+            Some(forced) => forced,
+            None if self.backtrace_env_control => env.is_some_and(|val| val != "0"),
+            None => true,
+        };
+        if self.background_resolver {
+            match spawn_background_resolver() {
+                Ok(tx) => state.resolver_tx = Some(tx),
+                Err(err) => {
+                    log_warn!(state, "failed to spawn background resolver thread: {}", err);
+                }
+            }
+        }
     }
 
     /// See [run_and_handle_panics].
     pub fn run_and_handle_panics<R: Debug>(
-        mut self,
+        self,
+        do_me: impl FnOnce() -> R + UnwindSafe,
+    ) -> Option<R> {
+        run_and_handle_panics_with_maybe_debug(Some(&self), do_me, format_debug)
+    }
+
+    /// Identical to [Builder::run_and_handle_panics] but borrows this builder, so the same
+    /// configuration can guard many scopes without cloning it each time.
+    pub fn run_and_handle_panics_ref<R: Debug>(
+        &self,
         do_me: impl FnOnce() -> R + UnwindSafe,
     ) -> Option<R> {
-        self.apply_settings();
-        run_and_handle_panics(do_me)
+        run_and_handle_panics_with_maybe_debug(Some(self), do_me, format_debug)
     }
 
     /// See [run_and_handle_panics_no_debug].
     pub fn run_and_handle_panics_no_debug<R>(
-        mut self,
+        self,
         do_me: impl FnOnce() -> R + UnwindSafe,
     ) -> Option<R> {
-        self.apply_settings();
-        run_and_handle_panics_no_debug(do_me)
+        run_and_handle_panics_with_maybe_debug(Some(&self), do_me, format_unprintable)
     }
-}
 
-impl Default for Builder {
-    fn default() -> Self {
-        Self::new()
+    /// See [run_and_handle_panics_with].
+    pub fn run_and_handle_panics_with<R>(
+        self,
+        do_me: impl FnOnce() -> R + UnwindSafe,
+        format_swallowed: impl FnOnce(R) -> Cow<'static, str>,
+    ) -> Option<R> {
+        run_and_handle_panics_with_maybe_debug(Some(&self), do_me, format_swallowed)
     }
-}
 
-fn register_panic(panic: &PanicInfo) {
-    let (thread, tid) = {
-        let t = std::thread::current();
-        let name = t.name().unwrap_or("<unnamed>");
-        (format!("{:?} ({})", t.id(), name), t.id())
-    };
+    /// See [run_and_handle_panics_result].
+    pub fn run_and_handle_panics_result<R: Debug>(
+        self,
+        do_me: impl FnOnce() -> R + UnwindSafe,
+    ) -> Result<R, PanicError> {
+        run_and_handle_panics_with_maybe_debug(Some(&self), do_me, format_debug)
+            .ok_or_else(|| PanicError(panics()))
+    }
 
-    // TODO use panic.message() when it stabilises
-    let message = panic
-        .payload()
-        .downcast_ref::<&str>()
-        .map(|s| Cow::Borrowed(*s))
-        .unwrap_or_else(|| Cow::from(format!("{}", panic)));
+    /// Sets the action to run with all panics when [run_and_handle_panics_strict] fails, before
+    /// the process exits. Defaults to nothing.
+    ///
+    /// The process still exits if the action returns.
+    pub fn failure_action(mut self, action: impl Fn(&[Panic]) + Send + Sync + 'static) -> Self {
+        self.failure_action = Some(Arc::new(action));
+        self
+    }
 
-    let backtrace = Backtrace::new_unresolved();
+    /// See [run_and_handle_panics_strict].
+    pub fn run_and_handle_panics_strict<R: Debug>(
+        self,
+        do_me: impl FnOnce() -> R + UnwindSafe,
+    ) -> R {
+        run_strict(Some(&self), do_me)
+    }
 
-    let mut state = state_mutex();
-    log_error!(&state, "handling panic on thread {}: '{}'", thread, message);
-
-    state.panics.push(Panic {
-        message: message.into_owned(),
-        thread_id: tid,
-        thread,
-        backtrace,
-        backtrace_resolved: false,
-    });
-}
+    /// Sets what happens when this scope is started while another is already running. Defaults to
+    /// [NestedPolicy::Panic].
+    pub fn on_nested(mut self, policy: NestedPolicy) -> Self {
+        self.nested_policy = policy;
+        self
+    }
 
-fn state_mutex() -> impl DerefMut<Target = State> {
-    #[cfg(feature = "use-parking-lot")]
-    return STATE.lock();
+    /// Suppresses all of panik's own logging in this scope, e.g. in tests where panics are
+    /// expected. Defaults to false.
+    ///
+    /// Panics are still captured and stored as normal.
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Prints panic reports to stderr when no logging feature (`use-log`, `use-slog` or
+    /// `use-stderr`) is enabled, so panics aren't captured invisibly. Defaults to true.
+    ///
+    /// Only the critical reports logged when a scope fails are printed. Disable this (or use
+    /// [Builder::quiet]) to truly silence panik without a logging feature. This has no effect if a
+    /// logging feature is enabled.
+    pub fn default_stderr_when_no_feature(mut self, enabled: bool) -> Self {
+        self.stderr_fallback = enabled;
+        self
+    }
+
+    /// Resolves backtraces on a background thread as soon as panics occur, rather than all at once
+    /// when the scope ends. Defaults to false.
+    ///
+    /// This is useful for long-lived scopes, so resolved backtraces are available from [panics]
+    /// soon after a panic occurs. The panicking thread still only captures an unresolved
+    /// backtrace. The stored panic is updated in place once resolved, without holding the lock
+    /// while resolving. [Builder::backtrace_resolution_limit] only applies to the resolution at
+    /// the end of the scope.
+    pub fn background_resolver(mut self, enabled: bool) -> Self {
+        self.background_resolver = enabled;
+        self
+    }
+
+    /// Tolerates up to `max` panics within any `window` of time, after which panics are fatal.
+    /// Defaults to no budget, i.e. any panic is fatal.
+    ///
+    /// Panics within the budget are still stored, but don't trip [has_panicked], and the closure's
+    /// result is still returned if it succeeded. Once the budget is exceeded, [budget_exceeded]
+    /// and [has_panicked] become true and the scope fails as normal.
+    pub fn panic_budget(mut self, max: usize, window: Duration) -> Self {
+        self.panic_budget = Some((max, window));
+        self
+    }
+
+    /// Appends a line describing each panic to the given file, so another process (e.g. a parent
+    /// supervising a sandboxed child) can learn of panics with [read_panics_from_file]. Defaults
+    /// to no file.
+    ///
+    /// Each line is written with a single append, so concurrent panics from multiple threads or
+    /// processes don't interleave.
+    pub fn panic_log_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.panic_log_file = Some(path.into());
+        self
+    }
+
+    #[cfg(target_os = "linux")]
+    /// Records each panic into a memory mapped ring buffer file holding the last `capacity`
+    /// panics, to be recovered with [read_persistent_ring] after the process restarts, e.g. if it
+    /// crashed before its logs were shipped. Defaults to none.
+    ///
+    /// Records are a fixed size, so long messages are truncated. The file is created if needed,
+    /// and reset if it isn't a ring buffer of the same capacity. It must not be shared between
+    /// processes running at the same time.
+    pub fn persistent_ring(mut self, path: impl Into<PathBuf>, capacity: usize) -> Self {
+        self.persistent_ring = Some((path.into(), capacity));
+        self
+    }
+
+    /// Ignores panics whose message contains any of the given strings, e.g. known-benign panics
+    /// from a noisy third-party library. Defaults to none.
+    ///
+    /// Suppressed panics are logged at debug level, and are neither stored nor fatal. If the
+    /// closure itself panics with a suppressed panic, the scope still returns `None`.
+    pub fn suppress_messages(mut self, messages: Vec<String>) -> Self {
+        self.suppressions
+            .extend(messages.into_iter().map(Suppression::Substring));
+        self
+    }
+
+    /// Like [Builder::suppress_messages], but matches messages with regular expressions.
+    #[cfg(feature = "use-regex")]
+    pub fn suppress_messages_matching(mut self, patterns: Vec<regex::Regex>) -> Self {
+        self.suppressions
+            .extend(patterns.into_iter().map(Suppression::Regex));
+        self
+    }
+
+    /// Like [Builder::suppress_messages], but loads the messages from a file at the path in the
+    /// given environment variable, one per line. This gives operators a way to suppress panics
+    /// without a recompile.
+    ///
+    /// The file is read when each scope begins. Nothing is loaded if the variable isn't set, and
+    /// a warning is logged if the file can't be read.
+    pub fn suppress_messages_from_env(mut self, var: impl Into<String>) -> Self {
+        self.suppressions_env_var = Some(var.into());
+        self
+    }
+
+    /// Sets a name for this scope, recorded on each panic it captures as [Panic::captured_by] and
+    /// included in log lines. Defaults to none.
+    ///
+    /// This disambiguates which scope captured a panic in layered setups, e.g. when a library
+    /// and the application both use panik.
+    pub fn scope_name(mut self, name: &'static str) -> Self {
+        self.scope_name = Some(name);
+        self
+    }
+
+    /// Warns if panics occurred in this scope but were never read with [panics], [take_panics] or
+    /// [snapshot] before the next scope begins, to catch forgetting to check the result. Defaults
+    /// to false.
+    ///
+    /// This is best-effort and only logs a warning, as the panics can only be read after the scope
+    /// has ended. Nothing is logged if no other scope is started.
+    pub fn require_handled(mut self, required: bool) -> Self {
+        self.require_handled = required;
+        self
+    }
+
+    /// Enters drain mode (see [enter_drain_mode]) as soon as the scope has failed, so cooperative
+    /// workers stop taking new work. Defaults to false.
+    ///
+    /// Panics tolerated by [Builder::soft_panic_threshold] or [Builder::panic_budget] don't
+    /// count, so drain mode is only entered once the scope is going to return `None`.
+    pub fn drain_on_first_panic(mut self, drain: bool) -> Self {
+        self.drain_on_first_panic = drain;
+        self
+    }
+
+    /// Logs a line at info level when the scope completes with no panics, e.g. for audit trails
+    /// that need to distinguish running cleanly from never running. Defaults to false.
+    pub fn log_clean_exit(mut self, log: bool) -> Self {
+        self.log_clean_exit = log;
+        self
+    }
+
+    /// Truncates panic messages longer than the given number of bytes, appending
+    /// `"…(truncated)"`, to bound memory and log size when something panics with a huge
+    /// dynamically built message. Defaults to no truncation.
+    ///
+    /// See [Panic::message_truncated].
+    pub fn max_message_len(mut self, max_len: usize) -> Self {
+        self.max_message_len = Some(max_len);
+        self
+    }
+
+    /// Caps the memory used by the backtraces of all stored panics. When a new panic pushes the
+    /// total over the cap, the backtraces of the oldest panics are dropped until it fits again,
+    /// keeping the rest of their details. Defaults to no cap.
+    ///
+    /// The size of a backtrace is only an estimate based on its frames and resolved symbols, not
+    /// exact accounting of allocations, and is counted when the panic is stored, so doesn't grow
+    /// if the backtrace is resolved later. See [backtraces_evicted].
+    pub fn max_total_backtrace_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_total_backtrace_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Sets the clock used for time windows, i.e. [Builder::panic_budget] and
+    /// [Builder::log_rate_limit]. Defaults to [Instant::now].
+    ///
+    /// This is mainly intended for deterministically testing time window logic with a fake
+    /// monotonic clock, without sleeping. It is called while panik's internal lock is held, so
+    /// must not call back into panik.
+    pub fn clock(mut self, clock: impl Fn() -> Instant + Send + Sync + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Sets the function used to capture the backtrace of a panic, e.g. a faster or capped-depth
+    /// unwinder. Defaults to [Backtrace::new_unresolved].
+    ///
+    /// This is called on the panicking thread without holding panik's internal lock, and only if
+    /// backtraces are being captured (see [Builder::capture_backtraces]). The returned backtrace
+    /// should be unresolved, as resolution is done later according to
+    /// [Builder::backtrace_resolution_limit].
+    pub fn backtrace_capturer(
+        mut self,
+        capturer: impl Fn() -> Backtrace + Send + Sync + 'static,
+    ) -> Self {
+        self.backtrace_capturer = Some(Arc::new(capturer));
+        self
+    }
+
+    /// Sets a callback to run exactly once with the first panic registered in a scope, e.g. to
+    /// begin a graceful shutdown without triggering it repeatedly during a storm of panics.
+    ///
+    /// This is called on the panicking thread without holding panik's internal lock, so may
+    /// query panik, but must not panic itself. As the callback can only be called once, it is
+    /// shared between clones of this builder and won't be called again in later scopes.
+    pub fn on_first_panic(mut self, f: impl FnOnce(&Panic) + Send + 'static) -> Self {
+        self.first_panic_fn = Some(Arc::new(std::sync::Mutex::new(Some(Box::new(f)))));
+        self
+    }
+
+    /// Sets a function to choose the process exit code based on the collected panics when
+    /// [run_and_handle_panics_strict] fails, e.g. to follow sysexits conventions. Defaults to
+    /// always exiting with code 1.
+    pub fn exit_code_classifier(
+        mut self,
+        classifier: impl Fn(&[Panic]) -> i32 + Send + Sync + 'static,
+    ) -> Self {
+        self.exit_code_classifier = Some(Arc::new(classifier));
+        self
+    }
+
+    /// Exits the process with the given code as soon as the thread that started the scope
+    /// panics, while panics on any other thread are only recorded as usual. Useful when workers
+    /// can die without harm but a panic on the main thread is unrecoverable.
+    ///
+    /// The panic is recorded and logged before exiting.
+    pub fn abort_on_main_thread_panic(mut self, exit_code: i32) -> Self {
+        self.abort_on_main_thread_panic = Some(exit_code);
+        self
+    }
+
+    /// Sets the function used to exit the process for [Builder::abort_on_main_thread_panic] and
+    /// [run_and_handle_panics_strict], e.g. to observe it in tests. Defaults to
+    /// [std::process::exit], which is still called if this returns from a strict scope.
+    pub fn exit_fn(mut self, f: impl Fn(i32) + Send + Sync + 'static) -> Self {
+        self.exit_fn = Some(Arc::new(f));
+        self
+    }
+
+    /// Sets a function to describe the thread a panic occurred on when it is reported, e.g.
+    /// "pool-worker handling task 4321" by consulting a thread pool's own registry. The
+    /// description is available in [Panic::thread_description], falling back to
+    /// [Panic::thread_name] if this returns `None`.
+    ///
+    /// This is called once per panic while the scope is being handled, rather than when the panic
+    /// is captured, and while panik's internal lock is held, so must not call back into panik.
+    pub fn thread_describer(
+        mut self,
+        describer: impl Fn(ThreadId) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.thread_describer = Some(Arc::new(describer));
+        self
+    }
+
+    /// Sets a function to decide whether a supervisor should restart the subsystem a panic
+    /// occurred in, e.g. true for transient I/O failures but false for assertion failures. The
+    /// decision is available in [Panic::restart_suggested], which is false if this isn't set.
+    ///
+    /// Like [Builder::thread_describer], this is called once per panic while the scope is being
+    /// handled and while panik's internal lock is held, so must not call back into panik.
+    pub fn restart_classifier(
+        mut self,
+        classifier: impl Fn(&Panic) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.restart_classifier = Some(Arc::new(classifier));
+        self
+    }
+
+    /// Sets a function to format thread IDs, e.g. "worker-7" for an application's own numeric
+    /// worker IDs instead of "ThreadId(12)". This is used in [Panic::thread_name] and
+    /// [Panic::summary_line], and so in panik's log output. Defaults to the `Debug` output of
+    /// [ThreadId].
+    ///
+    /// This is called on the panicking thread when a panic is captured, without holding panik's
+    /// internal lock, so must not panic itself.
+    pub fn thread_id_formatter(
+        mut self,
+        formatter: impl Fn(ThreadId) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.thread_id_formatter = Some(Arc::new(formatter));
+        self
+    }
+
+    /// Sets a function to describe panics whose payload isn't a string or a recognised number,
+    /// e.g. as a terse `"unknown panic payload"`. The result is used as [Panic::message]. Defaults
+    /// to the [PanicHookInfo]'s `Display` output followed by `"(unknown payload type ...)"` with
+    /// the payload's [TypeId](std::any::TypeId), as its type name can't be recovered.
+    ///
+    /// This is called on the panicking thread without holding panik's internal lock, so must not
+    /// panic itself.
+    pub fn unknown_payload_message(
+        mut self,
+        describe: impl Fn(&PanicHookInfo) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.unknown_payload_message = Some(Arc::new(describe));
+        self
+    }
+
+    /// Keeps recording panics after the scope ends into a separate bucket, available from
+    /// [post_scope_panics], e.g. to catch late worker panics during shutdown that would otherwise
+    /// go unnoticed. Defaults to false.
+    ///
+    /// These panics don't affect the result of the scope that has already returned, and are
+    /// otherwise handled by the panic hook installed before the scope. No backtraces are captured
+    /// for them. The bucket is cleared when the next scope begins.
+    pub fn capture_after_scope(mut self, capture: bool) -> Self {
+        self.capture_after_scope = capture;
+        self
+    }
+
+    #[cfg(target_os = "linux")]
+    /// Records a synthetic panic with the message `"stack overflow"` when a thread overflows its
+    /// stack, which otherwise aborts the process without panicking. Defaults to false.
+    ///
+    /// This installs `SIGSEGV` and `SIGBUS` handlers that run on the signal alternate stack, so
+    /// only works on threads that have one, i.e. those spawned by `std::thread` and the thread
+    /// that starts the scope. The handler only hands the overflow over to a background thread,
+    /// which records the panic as usual (e.g. logged and written to [Builder::panic_log_file]),
+    /// then the previous handler is restored and the process still aborts.
+    ///
+    /// Other faults are passed on to the previously installed handler. The handler can't safely
+    /// look up the overflowing thread's `std` handle, so the panic has the thread's OS name, and
+    /// a [Panic::thread_id] that matches no other thread.
+    ///
+    /// This is best effort: if the panic can't be recorded within a couple of seconds, e.g.
+    /// because the overflowing thread holds panik's internal lock, the process aborts without it.
+    /// Only the first overflow in the process is recorded.
+    pub fn catch_stack_overflow(mut self, catch: bool) -> Self {
+        self.catch_stack_overflow = catch;
+        self
+    }
+
+    #[cfg(feature = "use-ctrlc")]
+    /// Records a synthetic panic with the message `"interrupted"` on Ctrl-C (`SIGINT`, or the
+    /// console control events on Windows), so an interrupt takes the same graceful exit path as
+    /// a panic: [has_panicked] becomes true for the main loop to notice, and the scope then fails
+    /// as usual. Defaults to false.
+    ///
+    /// The handler is installed once for the whole process with the `ctrlc` crate, which runs it
+    /// on its own thread rather than in the signal handler, so recording the panic is safe. It is
+    /// left installed afterwards, and outside of a scope that enabled it exits the process with
+    /// code 130 as if it wasn't. Installing it fails if the application has already set its own
+    /// handler with the `ctrlc` crate, in which case a warning is logged.
+    pub fn treat_sigint_as_fatal(mut self, fatal: bool) -> Self {
+        self.treat_sigint_as_fatal = fatal;
+        self
+    }
+
+    #[cfg(feature = "use-log")]
+    /// Records `Error` level `log` records as "soft panics" while the scope runs, available from
+    /// [soft_panics], so severe logged errors can be detected like panics. Defaults to false.
+    ///
+    /// This requires the application's logger to be wrapped in an [ErrorLogCapture]. Soft panics
+    /// are kept separate from real panics, so don't affect [has_panicked] or the result of the
+    /// scope. Records logged by panik itself are ignored.
+    pub fn capture_error_logs(mut self, capture: bool) -> Self {
+        self.capture_error_logs = capture;
+        self
+    }
+
+    /// Forces backtrace capture on or off, taking precedence over
+    /// [Builder::backtrace_env_control]. By default this is left to the environment.
+    pub fn capture_backtraces(mut self, capture: bool) -> Self {
+        self.capture_backtraces = Some(capture);
+        self
+    }
+
+    /// Records how much CPU time the panicking thread had consumed, available from
+    /// [Panic::thread_cpu_time], e.g. to tell panics in hot loops apart from those in idle
+    /// handlers. This is only supported on Linux. Defaults to false.
+    pub fn capture_cpu_time(mut self, capture: bool) -> Self {
+        self.capture_cpu_time = capture;
+        self
+    }
+
+    /// Records how many threads were alive in the process when each panic occurred, available
+    /// from [Panic::live_thread_count]. This is only supported on Linux and Windows, and takes a
+    /// snapshot of the process's threads per panic, so defaults to false.
+    pub fn capture_live_thread_count(mut self, capture: bool) -> Self {
+        self.capture_live_thread_count = capture;
+        self
+    }
+
+    /// Stores only the first panic in the scope, ignoring any later ones entirely besides
+    /// counting them in [panics_dropped]. This is the cheapest mode when only the root cause
+    /// matters and later panics are assumed to be fallout from it. Defaults to false.
+    pub fn capture_first_only(mut self, first_only: bool) -> Self {
+        self.capture_first_only = first_only;
+        self
+    }
+
+    /// Lets the `RUST_LIB_BACKTRACE` and `RUST_BACKTRACE` environment variables control backtrace
+    /// capture like they do for the standard library's backtraces. Defaults to true.
+    ///
+    /// If enabled and not overridden by [Builder::capture_backtraces], backtraces are not captured
+    /// when the variable is unset or `0`. Unless overridden by [Builder::backtrace_format], they
+    /// are rendered with [BacktraceFormat::Full] when it's `full`, and [BacktraceFormat::Short]
+    /// for any other value. If disabled, backtraces are always captured and rendered in full.
+    ///
+    /// As in std, `RUST_LIB_BACKTRACE` takes precedence over `RUST_BACKTRACE` if set, so the order
+    /// of precedence is:
+    ///
+    /// 1. [Builder::capture_backtraces]
+    /// 2. `RUST_LIB_BACKTRACE`
+    /// 3. `RUST_BACKTRACE`
+    ///
+    /// The variables are read once when the scope starts.
+    pub fn backtrace_env_control(mut self, enabled: bool) -> Self {
+        self.backtrace_env_control = enabled;
+        self
+    }
+
+    /// Sets the maximum number of times to run the closure in
+    /// [Builder::run_and_handle_panics_with_retry]. Defaults to 1, i.e. no retries.
+    pub fn retry(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Identical to [Builder::run_and_handle_panics] except the closure is run again if any
+    /// thread(s) panicked, up to the number of attempts configured with [Builder::retry].
+    ///
+    /// Panics are cleared between attempts, so only those from the final attempt are available
+    /// in [panics]. The number of attempts made is available in [last_attempt_count].
+    pub fn run_and_handle_panics_with_retry<R: Debug>(
+        self,
+        do_me: impl Fn() -> R + RefUnwindSafe,
+    ) -> Option<R> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            state_mutex().last_attempt_count = attempt;
+
+            let result = run_and_handle_panics_with_maybe_debug(Some(&self), &do_me, format_debug);
+            if result.is_some() || attempt >= self.max_attempts {
+                return result;
+            }
+
+            log_warn!(
+                &state_mutex(),
+                "retrying after panics (attempt {}/{})",
+                attempt,
+                self.max_attempts
+            );
+        }
+    }
+
+    /// The limit on backtraces to resolve, see [Builder::backtrace_resolution_limit].
+    pub fn get_backtrace_resolution_limit(&self) -> usize {
+        self.backtrace_resolution_limit
+    }
+
+    /// The time budget for resolving backtraces, see
+    /// [Builder::backtrace_resolution_time_budget].
+    pub fn get_backtrace_resolution_time_budget(&self) -> Option<Duration> {
+        self.backtrace_resolution_time_budget
+    }
+
+    /// Whether panics are kept across scopes, see [Builder::preserve_panics_across_scopes].
+    pub fn get_preserve_panics_across_scopes(&self) -> bool {
+        self.preserve_panics
+    }
+
+    /// The grace period for in-flight panics, see [Builder::grace_period].
+    pub fn get_grace_period(&self) -> Duration {
+        self.grace_period
+    }
+
+    /// The maximum scope duration, see [Builder::max_scope_duration].
+    pub fn get_max_scope_duration(&self) -> Option<Duration> {
+        self.max_scope_duration
+    }
+
+    /// The per-second limit on logged panics, see [Builder::log_rate_limit].
+    pub fn get_log_rate_limit(&self) -> Option<u32> {
+        self.log_rate_limit
+    }
+
+    /// The number of panics considered fatal, see [Builder::soft_panic_threshold].
+    pub fn get_soft_panic_threshold(&self) -> usize {
+        self.soft_panic_threshold
+    }
+
+    /// Whether panic payloads are retained, see [Builder::retain_payload].
+    pub fn get_retain_payload(&self) -> bool {
+        self.retain_payload
+    }
+
+    /// The number of scopes to keep statistics for, see [Builder::scope_history_len].
+    pub fn get_scope_history_len(&self) -> usize {
+        self.scope_history_len
+    }
+
+    /// The build information for crash reports, see [Builder::build_info].
+    pub fn get_build_info(&self) -> Option<&BuildInfo> {
+        self.build_info.as_ref()
+    }
+
+    /// The environment variables to snapshot, see [Builder::capture_env_vars].
+    pub fn get_capture_env_vars(&self) -> &[String] {
+        &self.env_vars
+    }
+
+    /// How backtraces are rendered if set explicitly, see [Builder::backtrace_format].
+    pub fn get_backtrace_format(&self) -> Option<BacktraceFormat> {
+        self.backtrace_format
+    }
+
+    /// How much is logged about each panic, see [Builder::verbosity].
+    pub fn get_verbosity(&self) -> Verbosity {
+        self.verbosity
+    }
+
+    /// The maximum number of attempts, see [Builder::retry].
+    pub fn get_retry(&self) -> usize {
+        self.max_attempts
+    }
+
+    /// What happens when scopes are nested, see [Builder::on_nested].
+    pub fn get_on_nested(&self) -> NestedPolicy {
+        self.nested_policy
+    }
+
+    /// Whether panik's own logging is suppressed, see [Builder::quiet].
+    pub fn get_quiet(&self) -> bool {
+        self.quiet
+    }
+
+    /// Whether panic reports fall back to stderr without a logging feature, see
+    /// [Builder::default_stderr_when_no_feature].
+    pub fn get_default_stderr_when_no_feature(&self) -> bool {
+        self.stderr_fallback
+    }
+
+    /// Whether backtraces are resolved in the background, see [Builder::background_resolver].
+    pub fn get_background_resolver(&self) -> bool {
+        self.background_resolver
+    }
+
+    /// The panic budget as `(max, window)`, see [Builder::panic_budget].
+    pub fn get_panic_budget(&self) -> Option<(usize, Duration)> {
+        self.panic_budget
+    }
+
+    /// Whether panics after the scope ends are captured, see [Builder::capture_after_scope].
+    pub fn get_capture_after_scope(&self) -> bool {
+        self.capture_after_scope
+    }
+
+    #[cfg(target_os = "linux")]
+    /// Whether stack overflows are recorded, see [Builder::catch_stack_overflow].
+    pub fn get_catch_stack_overflow(&self) -> bool {
+        self.catch_stack_overflow
+    }
+
+    #[cfg(feature = "use-ctrlc")]
+    /// Whether Ctrl-C is recorded as a panic, see [Builder::treat_sigint_as_fatal].
+    pub fn get_treat_sigint_as_fatal(&self) -> bool {
+        self.treat_sigint_as_fatal
+    }
+
+    #[cfg(feature = "use-log")]
+    /// Whether error logs are recorded as soft panics, see [Builder::capture_error_logs].
+    pub fn get_capture_error_logs(&self) -> bool {
+        self.capture_error_logs
+    }
+
+    /// Whether backtrace capture is forced on or off, see [Builder::capture_backtraces].
+    pub fn get_capture_backtraces(&self) -> Option<bool> {
+        self.capture_backtraces
+    }
+
+    /// Whether thread CPU time is captured, see [Builder::capture_cpu_time].
+    pub fn get_capture_cpu_time(&self) -> bool {
+        self.capture_cpu_time
+    }
+
+    /// Whether the live thread count is captured, see [Builder::capture_live_thread_count].
+    pub fn get_capture_live_thread_count(&self) -> bool {
+        self.capture_live_thread_count
+    }
+
+    /// Whether only the first panic is stored, see [Builder::capture_first_only].
+    pub fn get_capture_first_only(&self) -> bool {
+        self.capture_first_only
+    }
+
+    /// Whether `RUST_LIB_BACKTRACE` and `RUST_BACKTRACE` control backtrace capture, see
+    /// [Builder::backtrace_env_control].
+    pub fn get_backtrace_env_control(&self) -> bool {
+        self.backtrace_env_control
+    }
+
+    /// The name of this scope, see [Builder::scope_name].
+    pub fn get_scope_name(&self) -> Option<&'static str> {
+        self.scope_name
+    }
+
+    /// The exit code used when the scope owner panics, see
+    /// [Builder::abort_on_main_thread_panic].
+    pub fn get_abort_on_main_thread_panic(&self) -> Option<i32> {
+        self.abort_on_main_thread_panic
+    }
+
+    /// Whether panics must be read before the next scope, see [Builder::require_handled].
+    pub fn get_require_handled(&self) -> bool {
+        self.require_handled
+    }
+
+    /// Whether drain mode is entered once the scope fails, see [Builder::drain_on_first_panic].
+    pub fn get_drain_on_first_panic(&self) -> bool {
+        self.drain_on_first_panic
+    }
+
+    /// Whether clean scope completion is logged, see [Builder::log_clean_exit].
+    pub fn get_log_clean_exit(&self) -> bool {
+        self.log_clean_exit
+    }
+
+    /// The maximum panic message length, see [Builder::max_message_len].
+    pub fn get_max_message_len(&self) -> Option<usize> {
+        self.max_message_len
+    }
+
+    /// The cap on total backtrace memory, see [Builder::max_total_backtrace_bytes].
+    pub fn get_max_total_backtrace_bytes(&self) -> Option<usize> {
+        self.max_total_backtrace_bytes
+    }
+
+    /// The file panics are appended to, see [Builder::panic_log_file].
+    pub fn get_panic_log_file(&self) -> Option<&Path> {
+        self.panic_log_file.as_deref()
+    }
+
+    #[cfg(target_os = "linux")]
+    /// The ring buffer file and its capacity, see [Builder::persistent_ring].
+    pub fn get_persistent_ring(&self) -> Option<(&Path, usize)> {
+        self.persistent_ring
+            .as_ref()
+            .map(|(path, capacity)| (path.as_path(), *capacity))
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns false if capture is paused, in which case the panic should be handled by the hook
+/// panik replaced instead, see [pause_capture].
+fn register_panic(panic: &PanicHookInfo) -> bool {
+    // taken before anything slow, e.g. capturing the backtrace
+    let epoch = PANIC_EPOCH.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+    let thread_id = std::thread::current().id();
+
+    // everything needed is copied out up front, so the lock is only taken again to store the panic
+    let (settings, describer, spawn_backtrace, registry) = {
+        let mut state = state_mutex();
+        if state.capture_paused {
+            log_debug!(&state, "capture is paused, ignoring panic");
+            return false;
+        }
+        // only applies to the global set, registries capture every panic on their threads
+        let registry = state.registries.get(&thread_id).cloned();
+        if registry.is_none() && state.capture_first_only && state.first_panic_captured {
+            state.panics_dropped += 1;
+            return true;
+        }
+        (
+            RegisterSettings {
+                capture_backtraces: state.capture_backtraces,
+                backtrace_capturer: state.backtrace_capturer.clone(),
+                breadcrumb_fn: state.breadcrumb_fn.clone(),
+                thread_cpu_time: if state.capture_cpu_time {
+                    current_thread_cpu_time()
+                } else {
+                    None
+                },
+                capture_live_thread_count: state.capture_live_thread_count,
+                suppressions: state.suppressions.clone(),
+                max_message_len: state.max_message_len,
+                log_target: state.log_target(),
+            },
+            state.panic_describer(thread_id),
+            state.spawn_sites.get(&thread_id).cloned(),
+            registry,
+        )
+    };
+
+    let (backtrace, possibly_ffi) = match (settings.capture_backtraces, settings.backtrace_capturer)
+    {
+        (false, _) => (None, false),
+        // a custom capturer may deliberately capture few frames
+        (true, Some(capturer)) => (Some(capturer()), false),
+        (true, None) => {
+            let backtrace = Backtrace::new_unresolved();
+            // unwinding through foreign frames without unwind info tends to truncate the backtrace
+            let possibly_ffi = backtrace.frames().len() < FFI_SUSPICIOUS_FRAME_COUNT;
+            (Some(backtrace), possibly_ffi)
+        }
+    };
+
+    let mut panic = Panic::from_panic_info_with_backtrace(panic, backtrace, &describer);
+    panic.possibly_ffi = possibly_ffi;
+    panic.thread_cpu_time = settings.thread_cpu_time;
+    if settings.capture_live_thread_count {
+        panic.live_thread_count = live_thread_count();
+    }
+    panic.epoch = epoch;
+    panic.spawn_backtrace = spawn_backtrace;
+
+    if settings
+        .suppressions
+        .iter()
+        .any(|s| s.matches(&panic.message))
+    {
+        log_debug!(
+            &settings.log_target,
+            "suppressing panic on thread {}: '{}'",
+            panic.thread,
+            panic.message
+        );
+        return true;
+    }
+
+    if let Some(max_len) = settings.max_message_len {
+        panic.truncate_message(max_len);
+    }
+
+    // called without holding the lock
+    if let Some(breadcrumb_fn) = settings.breadcrumb_fn {
+        panic.breadcrumbs = breadcrumb_fn();
+    }
+
+    #[cfg(feature = "use-metrics")]
+    metrics::counter!("panik.panics_total", "thread" => panic.thread_label.clone()).increment(1);
+
+    let mut state = state_mutex();
+    match registry {
+        Some(registry) => {
+            panic.captured_by = state.scope_name;
+            panic.sequence = state.next_sequence;
+            state.next_sequence += 1;
+            drop(state);
+
+            log_error!(
+                &settings.log_target,
+                "handling panic on thread {} in registry {:?}: '{}'",
+                panic.thread,
+                registry.name(),
+                panic.message
+            );
+
+            registry.panics_mutex().push(panic);
+        }
+        None => {
+            let exit = match state.abort_on_main_thread_panic {
+                Some(exit_code) if state.scope_owner == Some(thread_id) => {
+                    Some((exit_code, state.exit_fn.clone()))
+                }
+                _ => None,
+            };
+
+            store_panic_locked(state, panic);
+
+            if let Some((exit_code, exit_fn)) = exit {
+                exit_scope_owner(&settings.log_target, exit_code, exit_fn);
+            }
+        }
+    }
+
+    true
+}
+
+/// The address of a hook's closure, which identifies it as long as it's installed.
+fn hook_address(hook: &PanicHook) -> usize {
+    (&**hook as *const (dyn Fn(&PanicHookInfo) + Send + Sync)).cast::<()>() as usize
+}
+
+/// The settings [register_panic] needs, copied out of [State] to avoid holding the lock.
+struct RegisterSettings {
+    capture_backtraces: bool,
+    backtrace_capturer: Option<BacktraceCapturer>,
+    breadcrumb_fn: Option<BreadcrumbFn>,
+    thread_cpu_time: Option<Duration>,
+    capture_live_thread_count: bool,
+    suppressions: Vec<Suppression>,
+    max_message_len: Option<usize>,
+    log_target: LogTarget,
+}
+
+/// Exits the process after the scope owner panicked, see [Builder::abort_on_main_thread_panic].
+fn exit_scope_owner(log_target: &LogTarget, exit_code: i32, exit_fn: Option<ExitFn>) {
+    log_crit!(
+        log_target,
+        "scope owner thread panicked, exiting with code {}",
+        exit_code
+    );
+
+    match exit_fn {
+        Some(exit_fn) => exit_fn(exit_code),
+        None => std::process::exit(exit_code),
+    }
+}
+
+#[cfg(feature = "use-log")]
+fn register_soft_panic(record: &log::Record) {
+    if !state_mutex().capture_error_logs {
+        return;
+    }
+
+    let location = match (record.file(), record.line()) {
+        (Some(file), Some(line)) => Some(PanicLocation {
+            file: file.to_owned(),
+            line,
+            column: 0,
+        }),
+        _ => None,
+    };
+    let mut panic = Panic::on_current_thread(record.args().to_string(), location, None);
+
+    let mut state = state_mutex();
+    panic.captured_by = state.scope_name;
+    panic.sequence = state.next_sequence;
+    state.next_sequence += 1;
+    state.soft_panics.push(panic);
+}
+
+fn register_post_scope_panic(panic: &PanicHookInfo) {
+    let describer = state_mutex().panic_describer(std::thread::current().id());
+    let mut panic = Panic::from_panic_info_with_backtrace(panic, None, &describer);
+
+    let mut state = state_mutex();
+    panic.sequence = state.next_sequence;
+    state.next_sequence += 1;
+    state.post_scope_panics.push(panic);
+}
+
+fn store_panic(panic: Panic) {
+    store_panic_locked(state_mutex(), panic)
+}
+
+/// Like [store_panic], but with the lock already held. The lock is released before returning.
+fn store_panic_locked(mut state: impl DerefMut<Target = State>, mut panic: Panic) {
+    if state.capture_first_only {
+        // checked again as another thread may have got here first
+        if state.first_panic_captured {
+            state.panics_dropped += 1;
+            return;
+        }
+        state.first_panic_captured = true;
+    }
+
+    panic.backtrace_format = state.backtrace_format;
+    panic.captured_by = state.scope_name;
+    panic.sequence = state.next_sequence;
+    state.next_sequence += 1;
+
+    // formatted under the lock but logged after releasing it, as logging may block
+    let mut logs = Vec::new();
+
+    let now = state.now();
+    let should_log = match state.log_rate_limiter.as_mut() {
+        Some(limiter) => {
+            let (should_log, suppressed) = limiter.should_log(now);
+            if suppressed > 0 {
+                logs.push(DeferredLog::Warn(format!(
+                    "suppressed logging of {} panics",
+                    suppressed
+                )));
+            }
+            should_log
+        }
+        None => true,
+    };
+
+    if let Some((max, window)) = state.panic_budget {
+        state.budget_timestamps.push_back(now);
+        while let Some(oldest) = state.budget_timestamps.front() {
+            if now.duration_since(*oldest) > window {
+                state.budget_timestamps.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        if state.budget_timestamps.len() > max && !state.budget_exceeded {
+            state.budget_exceeded = true;
+            logs.push(DeferredLog::Error(format!(
+                "more than {} panics occurred within {:?}, panic budget exceeded",
+                max, window
+            )));
+        }
+    }
+
+    if let (Some(resolver), true) = (&state.resolver_tx, panic.backtrace_captured) {
+        let _ = resolver.send(panic.sequence);
+    }
+
+    #[cfg(feature = "use-tokio")]
+    {
+        if state.panic_sender.receiver_count() > 0 {
+            let _ = state.panic_sender.send(panic.clone());
+        }
+
+        if let Some(token) = state.cancellation_token.take() {
+            token.cancel();
+        }
+    }
+
+    if let Some(semaphore) = &state.panic_semaphore {
+        semaphore.release();
+    }
+
+    if should_log {
+        logs.push(DeferredLog::Error(match state.scope_name {
+            Some(scope) => format!(
+                "handling panic in scope {:?} on thread {}: '{}'",
+                scope, panic.thread, panic.message
+            ),
+            None => format!(
+                "handling panic on thread {}: '{}'",
+                panic.thread, panic.message
+            ),
+        }));
+    }
+
+    if let Some(path) = &state.panic_log_file {
+        if let Err(err) =
+            append_to_panic_log(path, &panic, state.build_info.as_ref(), &state.env_snapshot)
+        {
+            logs.push(DeferredLog::Warn(format!(
+                "failed to write panic to {}: {}",
+                path.display(),
+                err
+            )));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let state = &mut *state;
+        if let Some(ring) = state.persistent_ring.as_mut() {
+            ring.write(&panic, state.build_info.as_ref());
+        }
+    }
+
+    let first_panic = match state.first_panic_fn.take() {
+        None => {
+            state.push_panic(panic);
+            None
+        }
+        Some(first_panic_fn) => {
+            state.push_panic(panic.clone());
+            Some((first_panic_fn, panic))
+        }
+    };
+    state.update_has_panicked();
+    state.drain_if_failed();
+
+    let log_target = state.log_target();
+    drop(state);
+
+    for log in &logs {
+        log.log(&log_target);
+    }
+
+    // called without holding the lock
+    if let Some((first_panic_fn, panic)) = first_panic {
+        let f = first_panic_fn
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(f) = f {
+            f(&panic);
+        }
+    }
+}
+
+/// A rough estimate of the memory used by a backtrace, see [Builder::max_total_backtrace_bytes].
+fn estimated_backtrace_bytes(backtrace: &Backtrace) -> usize {
+    backtrace
+        .frames()
+        .iter()
+        .map(|frame| {
+            let symbols = frame
+                .symbols()
+                .iter()
+                .map(|symbol| {
+                    std::mem::size_of::<BacktraceSymbol>()
+                        + symbol.name().map_or(0, |name| name.as_bytes().len())
+                        + symbol.filename().map_or(0, |path| path.as_os_str().len())
+                })
+                .sum::<usize>();
+            std::mem::size_of::<BacktraceFrame>() + symbols
+        })
+        .sum()
+}
+
+/// Whether all symbols in the frame belong to the standard library or runtime, and so are omitted
+/// from [BacktraceFormat::Short] backtraces.
+fn is_runtime_frame(frame: &BacktraceFrame) -> bool {
+    const RUNTIME_CRATES: &[&str] = &["std", "core", "alloc", "backtrace", "panik", "test"];
+    const RUNTIME_SYMBOLS: &[&str] = &[
+        "__rust",
+        "rust_begin_unwind",
+        "_start",
+        "__libc_start",
+        "start_thread",
+        "__clone",
+        "clone3",
+    ];
+
+    let symbols = frame.symbols();
+    !symbols.is_empty()
+        && symbols.iter().all(|symbol| {
+            let name = match symbol.name() {
+                Some(name) => name.to_string(),
+                None => return false,
+            };
+
+            match symbol_crate(&name) {
+                Some(krate) => RUNTIME_CRATES.contains(&krate),
+                None => RUNTIME_SYMBOLS
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix)),
+            }
+        })
+}
+
+/// Gets the crate from a demangled symbol name, e.g. `std` from `std::panicking::begin_panic` or
+/// `<std[a1b2c3]::thread::Thread as core::fmt::Debug>::fmt`.
+fn symbol_crate(name: &str) -> Option<&str> {
+    let (krate, _) = name.trim_start_matches('<').split_once("::")?;
+
+    // strip disambiguating hash from v0 mangling
+    let krate = krate.split('[').next().unwrap_or(krate);
+    Some(krate)
+}
+
+/// Gets the crate of the first symbol outside of the standard library and runtime.
+fn origin_crate(backtrace: &Backtrace) -> Option<String> {
+    backtrace
+        .frames()
+        .iter()
+        .filter(|frame| !is_runtime_frame(frame))
+        .flat_map(|frame| frame.symbols())
+        .find_map(|symbol| {
+            let name = symbol.name()?.to_string();
+            symbol_crate(&name).map(str::to_owned)
+        })
+}
+
+/// Renders the message of common payload types, and names the type if recognised. A `None`
+/// message means it should be taken from the panic info instead.
+fn describe_payload(payload: &(dyn Any + Send)) -> (Option<Cow<'_, str>>, Option<&'static str>) {
+    fn numeric<T: Any + Display>(
+        payload: &(dyn Any + Send),
+    ) -> Option<(Option<Cow<'static, str>>, Option<&'static str>)> {
+        payload.downcast_ref::<T>().map(|n| {
+            (
+                Some(Cow::Owned(n.to_string())),
+                Some(std::any::type_name::<T>()),
+            )
+        })
+    }
+
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        return (Some(Cow::Borrowed(*s)), Some(std::any::type_name::<&str>()));
+    }
+
+    if payload.is::<String>() {
+        return (None, Some(std::any::type_name::<String>()));
+    }
+
+    numeric::<i32>(payload)
+        .or_else(|| numeric::<u32>(payload))
+        .or_else(|| numeric::<i64>(payload))
+        .or_else(|| numeric::<u64>(payload))
+        .unwrap_or((None, None))
+}
+
+fn append_to_panic_log(
+    path: &Path,
+    panic: &Panic,
+    build_info: Option<&BuildInfo>,
+    env: &[(String, Option<String>)],
+) -> std::io::Result<()> {
+    let entry = format!(
+        "{}\n",
+        panic_log_line(panic, &panic.message, build_info, env)
+    );
+
+    // a single write in append mode so lines aren't interleaved
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(entry.as_bytes())
+}
+
+/// Formats a tab separated line of `pid, sequence, thread, file, line, column, message`, with an
+/// empty file, line and column if the location is unknown. This is followed by the build's
+/// `version, git sha, build timestamp` if known, then a `NAME=value` field (or just `NAME` if
+/// unset) per captured environment variable. The message is passed separately so it can be
+/// shortened to fit.
+fn panic_log_line(
+    panic: &Panic,
+    message: &str,
+    build_info: Option<&BuildInfo>,
+    env: &[(String, Option<String>)],
+) -> String {
+    let (file, line, column) = match &panic.location {
+        Some(loc) => (
+            escape_log_field(&loc.file),
+            loc.line.to_string(),
+            loc.column.to_string(),
+        ),
+        None => Default::default(),
+    };
+
+    let mut entry = format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        std::process::id(),
+        panic.sequence,
+        escape_log_field(&panic.thread),
+        file,
+        line,
+        column,
+        escape_log_field(message),
+    );
+
+    // environment variables can only follow the build, so an empty one is written if needed
+    if build_info.is_some() || !env.is_empty() {
+        let build_info = build_info.cloned().unwrap_or_default();
+        for field in [
+            &build_info.version,
+            &build_info.git_sha,
+            &build_info.build_timestamp,
+        ] {
+            entry.push('\t');
+            entry.push_str(&escape_log_field(field));
+        }
+    }
+
+    for (name, value) in env {
+        entry.push('\t');
+        entry.push_str(&escape_log_field(name));
+        if let Some(value) = value {
+            entry.push('=');
+            entry.push_str(&escape_log_field(value));
+        }
+    }
+
+    entry
+}
+
+/// Formats a panic as in [panic_log_line] without the environment, which doesn't fit in a fixed
+/// size record, shortening the message until it fits in `max_len` bytes. Returns `None` if it
+/// doesn't fit even with an empty message.
+fn panic_log_line_within(
+    panic: &Panic,
+    build_info: Option<&BuildInfo>,
+    max_len: usize,
+) -> Option<String> {
+    let mut message = panic.message.as_str();
+    loop {
+        let line = panic_log_line(panic, message, build_info, &[]);
+        if line.len() <= max_len {
+            return Some(line);
+        }
+
+        if message.is_empty() {
+            return None;
+        }
+
+        let mut end = message.len().saturating_sub(line.len() - max_len);
+        while !message.is_char_boundary(end) {
+            end -= 1;
+        }
+        message = &message[..end];
+    }
+}
+
+/// 32-bit FNV-1a, to detect torn or corrupted [Builder::persistent_ring] records.
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(*byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Reads the capacity from the header of a [Builder::persistent_ring] file, if valid.
+fn parse_ring_header(bytes: &[u8]) -> Option<usize> {
+    let header = bytes.get(..RING_HEADER_SIZE)?;
+    let record_size = u32::from_le_bytes(header[8..12].try_into().ok()?) as usize;
+    let capacity = u32::from_le_bytes(header[12..16].try_into().ok()?) as usize;
+
+    let valid = &header[..8] == RING_MAGIC
+        && record_size == RING_RECORD_SIZE
+        && capacity > 0
+        && bytes.len() >= RING_HEADER_SIZE + capacity * RING_RECORD_SIZE;
+    if valid {
+        Some(capacity)
+    } else {
+        None
+    }
+}
+
+/// Parses a [Builder::persistent_ring] record into its write index and panic, or `None` if it's
+/// empty or corrupt.
+fn parse_ring_record(record: &[u8]) -> Option<(u64, PanicLite)> {
+    let len = u32::from_le_bytes(record[..4].try_into().ok()?) as usize;
+    let checksum = u32::from_le_bytes(record[4..8].try_into().ok()?);
+    let index = u64::from_le_bytes(record[8..16].try_into().ok()?);
+
+    let payload = record.get(RING_RECORD_HEADER_SIZE..RING_RECORD_HEADER_SIZE + len)?;
+    if len == 0 || fnv1a(payload) != checksum {
+        return None;
+    }
+
+    let line = std::str::from_utf8(payload).ok()?;
+    PanicLite::parse(line).map(|panic| (index, panic))
+}
+
+/// A memory mapped ring buffer of fixed size records, see [Builder::persistent_ring].
+///
+/// The file starts with a header of [RING_MAGIC], the record size and capacity as `u32`s, and the
+/// total number of records ever written as a `u64`, all little endian. Each record is the payload
+/// length and [fnv1a] checksum as `u32`s, its write index as a `u64`, then a [panic_log_line].
+#[cfg(target_os = "linux")]
+struct PersistentRing {
+    map: *mut u8,
+    len: usize,
+    capacity: usize,
+}
+
+// safety: the mapping is only accessed through State, under its lock
+#[cfg(target_os = "linux")]
+unsafe impl Send for PersistentRing {}
+
+#[cfg(target_os = "linux")]
+impl PersistentRing {
+    fn open(path: &Path, capacity: usize) -> std::io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        if capacity == 0 || capacity > u32::MAX as usize {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid ring buffer capacity",
+            ));
+        }
+
+        let len = RING_HEADER_SIZE + capacity * RING_RECORD_SIZE;
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        file.set_len(len as u64)?;
+
+        // safety: the file is exactly len bytes long, and the mapping outlives the file handle
+        let map = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if map == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let mut ring = PersistentRing {
+            map: map.cast(),
+            len,
+            capacity,
+        };
+
+        // start afresh if this isn't a ring buffer with the same layout
+        if parse_ring_header(ring.bytes()) != Some(capacity) {
+            let bytes = ring.bytes_mut();
+            bytes.fill(0);
+            bytes[..8].copy_from_slice(RING_MAGIC);
+            bytes[8..12].copy_from_slice(&(RING_RECORD_SIZE as u32).to_le_bytes());
+            bytes[12..16].copy_from_slice(&(capacity as u32).to_le_bytes());
+        }
+
+        Ok(ring)
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // safety: map is a live mapping of len bytes
+        unsafe { std::slice::from_raw_parts(self.map, self.len) }
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        // safety: map is a live writable mapping of len bytes, borrowed mutably through self
+        unsafe { std::slice::from_raw_parts_mut(self.map, self.len) }
+    }
+
+    fn write(&mut self, panic: &Panic, build_info: Option<&BuildInfo>) {
+        let max_len = RING_RECORD_SIZE - RING_RECORD_HEADER_SIZE;
+        let Some(line) = panic_log_line_within(panic, build_info, max_len) else {
+            return;
+        };
+
+        let capacity = self.capacity;
+        let bytes = self.bytes_mut();
+        let index = u64::from_le_bytes(bytes[16..24].try_into().unwrap());
+        let offset = RING_HEADER_SIZE + (index % capacity as u64) as usize * RING_RECORD_SIZE;
+
+        let record = &mut bytes[offset..offset + RING_RECORD_SIZE];
+        record.fill(0);
+        record[..4].copy_from_slice(&(line.len() as u32).to_le_bytes());
+        record[4..8].copy_from_slice(&fnv1a(line.as_bytes()).to_le_bytes());
+        record[8..16].copy_from_slice(&index.to_le_bytes());
+        record[RING_RECORD_HEADER_SIZE..RING_RECORD_HEADER_SIZE + line.len()]
+            .copy_from_slice(line.as_bytes());
+
+        bytes[16..24].copy_from_slice(&(index + 1).to_le_bytes());
+
+        // safety: map is a live mapping of len bytes
+        unsafe {
+            libc::msync(self.map.cast(), self.len, libc::MS_ASYNC);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for PersistentRing {
+    fn drop(&mut self) {
+        // safety: map is a live mapping of len bytes, and is never used again
+        unsafe {
+            libc::munmap(self.map.cast(), self.len);
+        }
+    }
+}
+
+fn escape_log_field(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape_log_field(field: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(unescaped)
+}
+
+#[cfg(target_os = "linux")]
+fn current_thread_stack_size() -> Option<usize> {
+    // safety: pthread_self has no preconditions
+    thread_stack_size(unsafe { libc::pthread_self() })
+}
+
+#[cfg(target_os = "linux")]
+fn thread_stack_size(thread: libc::pthread_t) -> Option<usize> {
+    let mut attr = std::mem::MaybeUninit::<libc::pthread_attr_t>::uninit();
+
+    // safety: attr is only read after being initialised by pthread_getattr_np, and is destroyed
+    // afterwards
+    unsafe {
+        if libc::pthread_getattr_np(thread, attr.as_mut_ptr()) != 0 {
+            return None;
+        }
+
+        let mut size = 0;
+        let ret = libc::pthread_attr_getstacksize(attr.as_ptr(), &mut size);
+        libc::pthread_attr_destroy(attr.as_mut_ptr());
+
+        if ret == 0 {
+            Some(size)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_thread_stack_size() -> Option<usize> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn current_thread_cpu_time() -> Option<Duration> {
+    let mut time = std::mem::MaybeUninit::<libc::timespec>::uninit();
+
+    // safety: time is only read after being initialised by clock_gettime
+    unsafe {
+        if libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, time.as_mut_ptr()) != 0 {
+            return None;
+        }
+
+        let time = time.assume_init();
+        Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// The signal handlers replaced by [install_stack_overflow_handler], restored once a stack
+/// overflow has been recorded so the fault is then handled as usual.
+#[cfg(target_os = "linux")]
+struct PreviousSignalHandlers {
+    sigsegv: libc::sigaction,
+    sigbus: libc::sigaction,
+}
+
+// safety: only read after being set once
+#[cfg(target_os = "linux")]
+unsafe impl Send for PreviousSignalHandlers {}
+#[cfg(target_os = "linux")]
+unsafe impl Sync for PreviousSignalHandlers {}
+
+#[cfg(target_os = "linux")]
+static PREVIOUS_SIGNAL_HANDLERS: std::sync::OnceLock<PreviousSignalHandlers> =
+    std::sync::OnceLock::new();
+
+/// Installs the handler for [Builder::treat_sigint_as_fatal] once for the whole process.
+#[cfg(feature = "use-ctrlc")]
+fn install_sigint_handler(state: &State) {
+    static INSTALL: std::sync::Once = std::sync::Once::new();
+    INSTALL.call_once(|| {
+        if let Err(err) = ctrlc::set_handler(record_interrupt) {
+            log_warn!(state, "failed to install Ctrl-C handler: {}", err);
+        }
+    });
+}
+
+/// Runs on the `ctrlc` crate's handler thread, not in a signal handler.
+#[cfg(feature = "use-ctrlc")]
+fn record_interrupt() {
+    let enabled = {
+        let state = state_mutex();
+        let enabled = state.is_running && state.treat_sigint_as_fatal;
+        if enabled {
+            log_warn!(&state, "interrupted, treating as a panic");
+        }
+        enabled
+    };
+
+    if !enabled {
+        // behave as if the handler was never installed
+        std::process::exit(SIGINT_EXIT_CODE);
+    }
+
+    store_panic(Panic::on_current_thread(
+        "interrupted".to_owned(),
+        None,
+        None,
+    ));
+}
+
+/// Installs the handlers for [Builder::catch_stack_overflow] once for the whole process, along
+/// with the thread that records overflows for them. They are left installed afterwards, and do
+/// nothing outside of a scope that enabled them.
+#[cfg(target_os = "linux")]
+fn install_stack_overflow_handler(state: &State) {
+    static INSTALL: std::sync::Once = std::sync::Once::new();
+    INSTALL.call_once(|| {
+        let recorder = match StackOverflowRecorder::spawn() {
+            Ok(recorder) => recorder,
+            Err(err) => {
+                log_warn!(state, "failed to start stack overflow recorder: {}", err);
+                return;
+            }
+        };
+        let _ = STACK_OVERFLOW_RECORDER.set(recorder);
+
+        // safety: the sigaction structs are fully initialised by zeroing and sigemptyset, and the
+        // previous handlers are stored before ours can run
+        unsafe {
+            let mut previous = PreviousSignalHandlers {
+                sigsegv: std::mem::zeroed(),
+                sigbus: std::mem::zeroed(),
+            };
+            libc::sigaction(libc::SIGSEGV, std::ptr::null(), &mut previous.sigsegv);
+            libc::sigaction(libc::SIGBUS, std::ptr::null(), &mut previous.sigbus);
+            let _ = PREVIOUS_SIGNAL_HANDLERS.set(previous);
+
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handle_stack_overflow as *const () as usize;
+            action.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(libc::SIGSEGV, &action, std::ptr::null_mut());
+            libc::sigaction(libc::SIGBUS, &action, std::ptr::null_mut());
+        }
+    });
+
+    ensure_signal_stack();
+}
+
+/// The size of the signal stack installed by [ensure_signal_stack], comfortably more than
+/// [handle_stack_overflow] needs.
+#[cfg(target_os = "linux")]
+const SIGNAL_STACK_SIZE: usize = 64 * 1024;
+
+/// How long [handle_stack_overflow] waits for the overflow to be recorded before letting the
+/// process die regardless, e.g. if the overflowing thread holds panik's lock.
+#[cfg(target_os = "linux")]
+const STACK_OVERFLOW_RECORD_TIMEOUT_MS: libc::c_int = 2000;
+
+/// A signal stack installed by panik for the current thread, see [ensure_signal_stack].
+#[cfg(target_os = "linux")]
+struct SignalStack {
+    base: *mut libc::c_void,
+    size: usize,
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for SignalStack {
+    fn drop(&mut self) {
+        // safety: the stack was mapped by ensure_signal_stack, and is only disabled if it is
+        // still the one in use
+        unsafe {
+            let mut current: libc::stack_t = std::mem::zeroed();
+            libc::sigaltstack(std::ptr::null(), &mut current);
+            if current.ss_sp == self.base {
+                let disable = libc::stack_t {
+                    ss_sp: std::ptr::null_mut(),
+                    ss_flags: libc::SS_DISABLE,
+                    ss_size: 0,
+                };
+                libc::sigaltstack(&disable, std::ptr::null_mut());
+            }
+            libc::munmap(self.base, self.size);
+        }
+    }
+}
+
+/// Gives the current thread a dedicated signal stack of [SIGNAL_STACK_SIZE] for
+/// [handle_stack_overflow] to run on, unless it already has one at least that large. Other threads
+/// spawned by `std::thread` use the smaller one std gives them, which is plenty now the handler
+/// only does async-signal-safe work.
+#[cfg(target_os = "linux")]
+fn ensure_signal_stack() {
+    thread_local! {
+        static SIGNAL_STACK: Cell<Option<SignalStack>> = const { Cell::new(None) };
+    }
+
+    // safety: the new stack is only installed once successfully mapped, and unmapped when the
+    // thread exits
+    unsafe {
+        let mut current: libc::stack_t = std::mem::zeroed();
+        if libc::sigaltstack(std::ptr::null(), &mut current) != 0 {
+            return;
+        }
+        if current.ss_flags & libc::SS_DISABLE == 0 && current.ss_size >= SIGNAL_STACK_SIZE {
+            return;
+        }
+
+        let base = libc::mmap(
+            std::ptr::null_mut(),
+            SIGNAL_STACK_SIZE,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        );
+        if base == libc::MAP_FAILED {
+            return;
+        }
+
+        let stack = libc::stack_t {
+            ss_sp: base,
+            ss_flags: 0,
+            ss_size: SIGNAL_STACK_SIZE,
+        };
+        if libc::sigaltstack(&stack, std::ptr::null_mut()) != 0 {
+            libc::munmap(base, SIGNAL_STACK_SIZE);
+            return;
+        }
+
+        SIGNAL_STACK.with(|signal_stack| {
+            signal_stack.set(Some(SignalStack {
+                base,
+                size: SIGNAL_STACK_SIZE,
+            }))
+        });
+    }
+}
+
+/// Records stack overflows on behalf of [handle_stack_overflow], which can't do so itself as it
+/// runs on a small signal stack in a thread that may hold any lock.
+///
+/// The handler claims a pre-allocated slot, leaves the faulting thread's raw IDs and address in
+/// it, wakes the recorder thread through a pipe, then waits on another pipe for its verdict. The
+/// slot is released again if the fault turns out not to be a stack overflow, so only the first
+/// overflow is handled.
+#[cfg(target_os = "linux")]
+struct StackOverflowRecorder {
+    claimed: std::sync::atomic::AtomicBool,
+    fault: std::sync::atomic::AtomicUsize,
+    pthread: std::sync::atomic::AtomicU64,
+    tid: std::sync::atomic::AtomicI32,
+    /// Written by the handler to wake the recorder thread.
+    notify_tx: libc::c_int,
+    /// Read by the handler to wait for the recorder thread.
+    done_rx: libc::c_int,
+    /// Identifies overflow panics, as the faulting thread's [ThreadId] can't be looked up from
+    /// the handler. This is the recorder thread's own, so matches no other thread.
+    thread_id: ThreadId,
+}
+
+#[cfg(target_os = "linux")]
+static STACK_OVERFLOW_RECORDER: std::sync::OnceLock<StackOverflowRecorder> =
+    std::sync::OnceLock::new();
+
+/// Sent by the recorder thread to [StackOverflowRecorder::hand_over] once the fault has been
+/// checked.
+#[cfg(target_os = "linux")]
+const NOT_STACK_OVERFLOW: u8 = 0;
+#[cfg(target_os = "linux")]
+const STACK_OVERFLOW: u8 = 1;
+
+#[cfg(target_os = "linux")]
+impl StackOverflowRecorder {
+    fn spawn() -> std::io::Result<Self> {
+        let (notify_rx, notify_tx) = cloexec_pipe()?;
+        let (done_rx, done_tx) = cloexec_pipe()?;
+
+        let thread = std::thread::Builder::new()
+            .name("panik-overflow".to_owned())
+            .spawn(move || loop {
+                let mut byte = 0u8;
+                // safety: the pipe is valid for the life of the process
+                if unsafe { libc::read(notify_rx, (&mut byte as *mut u8).cast(), 1) } != 1 {
+                    if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+                        continue;
+                    }
+                    return;
+                }
+
+                let verdict = match STACK_OVERFLOW_RECORDER.get() {
+                    Some(recorder) if recorder.record() => STACK_OVERFLOW,
+                    _ => NOT_STACK_OVERFLOW,
+                };
+
+                // safety: as above
+                unsafe {
+                    libc::write(done_tx, (&verdict as *const u8).cast(), 1);
+                }
+            })?;
+
+        Ok(StackOverflowRecorder {
+            claimed: std::sync::atomic::AtomicBool::new(false),
+            fault: std::sync::atomic::AtomicUsize::new(0),
+            pthread: std::sync::atomic::AtomicU64::new(0),
+            tid: std::sync::atomic::AtomicI32::new(0),
+            notify_tx,
+            done_rx,
+            thread_id: thread.thread().id(),
+        })
+    }
+
+    /// Called from the signal handler, so must only do async-signal-safe work. Returns whether
+    /// the fault was a stack overflow, or `None` if it couldn't be handed over.
+    fn hand_over(&self, fault: usize) -> Option<bool> {
+        use std::sync::atomic::Ordering;
+
+        if self.claimed.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+
+        self.fault.store(fault, Ordering::SeqCst);
+        // safety: pthread_self and gettid have no preconditions
+        unsafe {
+            self.pthread
+                .store(libc::pthread_self() as u64, Ordering::SeqCst);
+            self.tid.store(
+                libc::syscall(libc::SYS_gettid) as libc::pid_t,
+                Ordering::SeqCst,
+            );
+        }
+
+        // safety: both pipes are valid for the life of the process, and write, poll and read are
+        // async-signal-safe
+        let verdict = unsafe {
+            let byte = 1u8;
+            if libc::write(self.notify_tx, (&byte as *const u8).cast(), 1) != 1 {
+                self.claimed.store(false, Ordering::SeqCst);
+                return None;
+            }
+
+            let mut done = libc::pollfd {
+                fd: self.done_rx,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let mut verdict = STACK_OVERFLOW;
+            if libc::poll(&mut done, 1, STACK_OVERFLOW_RECORD_TIMEOUT_MS) == 1 {
+                libc::read(self.done_rx, (&mut verdict as *mut u8).cast(), 1);
+            }
+            // on timeout the slot stays claimed, as the recorder thread may still be reading it
+            verdict
+        };
+
+        if verdict == NOT_STACK_OVERFLOW {
+            self.claimed.store(false, Ordering::SeqCst);
+            return Some(false);
+        }
+
+        Some(true)
+    }
+
+    /// Called on the recorder thread once woken by [StackOverflowRecorder::hand_over], while
+    /// the faulting thread waits in the signal handler. Returns whether the fault was a stack
+    /// overflow, regardless of whether it was recorded.
+    fn record(&self) -> bool {
+        use std::sync::atomic::Ordering;
+
+        let pthread = self.pthread.load(Ordering::SeqCst) as libc::pthread_t;
+        if !is_stack_guard_fault(pthread, self.fault.load(Ordering::SeqCst)) {
+            return false;
+        }
+
+        let describer = {
+            let state = state_mutex();
+            if !(state.is_running && state.catch_stack_overflow) {
+                return true;
+            }
+            state.panic_describer(self.thread_id)
+        };
+
+        let name = os_thread_name(self.tid.load(Ordering::SeqCst));
+        store_panic(Panic::on_thread(
+            self.thread_id,
+            name.as_deref(),
+            thread_stack_size(pthread),
+            &describer,
+            "stack overflow".to_owned(),
+            None,
+            None,
+        ));
+        true
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn cloexec_pipe() -> std::io::Result<(libc::c_int, libc::c_int)> {
+    let mut fds = [0; 2];
+    // safety: fds has room for both ends
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok((fds[0], fds[1]))
+}
+
+/// The name the OS has for the given thread, or `None` if it was never named and so still has
+/// the process's name.
+#[cfg(target_os = "linux")]
+fn os_thread_name(tid: libc::pid_t) -> Option<String> {
+    // safety: getpid has no preconditions
+    if tid == unsafe { libc::getpid() } {
+        return Some("main".to_owned());
+    }
+
+    let read_comm = |path: &str| {
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|comm| comm.trim_end().to_owned())
+    };
+    let name = read_comm(&format!("/proc/self/task/{}/comm", tid))?;
+    if name.is_empty() || Some(&name) == read_comm("/proc/self/comm").as_ref() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// How far the faulting address may be from the interrupted stack pointer for
+/// [handle_stack_overflow] to suspect a stack overflow, covering a frame's worth of stack probes
+/// and locals.
+#[cfg(target_os = "linux")]
+const STACK_OVERFLOW_SP_DISTANCE: usize = 64 * 1024;
+
+/// A cheap check from the signal handler of whether a fault could be a stack overflow, i.e. is
+/// near the interrupted thread's stack pointer. The recorder thread then checks the guard page
+/// precisely. Always true where the stack pointer can't be read from the context.
+#[cfg(target_os = "linux")]
+fn near_stack_pointer(fault: usize, context: *mut libc::c_void) -> bool {
+    match interrupted_stack_pointer(context) {
+        Some(sp) => fault.abs_diff(sp) <= STACK_OVERFLOW_SP_DISTANCE,
+        None => true,
+    }
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn interrupted_stack_pointer(context: *mut libc::c_void) -> Option<usize> {
+    // safety: context is the ucontext_t provided by the kernel for SA_SIGINFO handlers
+    let context = unsafe { &*context.cast::<libc::ucontext_t>() };
+    Some(context.uc_mcontext.gregs[libc::REG_RSP as usize] as usize)
+}
+
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+fn interrupted_stack_pointer(context: *mut libc::c_void) -> Option<usize> {
+    // safety: context is the ucontext_t provided by the kernel for SA_SIGINFO handlers
+    let context = unsafe { &*context.cast::<libc::ucontext_t>() };
+    Some(context.uc_mcontext.sp as usize)
+}
+
+#[cfg(all(
+    target_os = "linux",
+    not(any(target_arch = "x86_64", target_arch = "aarch64"))
+))]
+fn interrupted_stack_pointer(_: *mut libc::c_void) -> Option<usize> {
+    None
+}
+
+/// Runs on the signal stack of the faulting thread, so only does async-signal-safe work and
+/// leaves the rest to [StackOverflowRecorder]. Faults that aren't stack overflows are passed on
+/// to the previous handler, leaving this one installed.
+#[cfg(target_os = "linux")]
+extern "C" fn handle_stack_overflow(
+    signum: libc::c_int,
+    info: *mut libc::siginfo_t,
+    context: *mut libc::c_void,
+) {
+    let Some(previous) = PREVIOUS_SIGNAL_HANDLERS.get() else {
+        return;
+    };
+    let previous = if signum == libc::SIGBUS {
+        &previous.sigbus
+    } else {
+        &previous.sigsegv
+    };
+
+    // safety: info is provided by the kernel for SA_SIGINFO handlers
+    let fault = unsafe { (*info).si_addr() } as usize;
+    let overflowed = near_stack_pointer(fault, context)
+        && STACK_OVERFLOW_RECORDER
+            .get()
+            .and_then(|recorder| recorder.hand_over(fault))
+            .unwrap_or(false);
+
+    // safety: previous is a valid sigaction read from the kernel, and its handler is called with
+    // the arguments it was registered for
+    unsafe {
+        let handler = previous.sa_sigaction;
+        if overflowed || handler == libc::SIG_DFL || handler == libc::SIG_IGN {
+            // returning retries the faulting instruction, which is then handled by the previous
+            // handler
+            libc::sigaction(signum, previous, std::ptr::null_mut());
+        } else if previous.sa_flags & libc::SA_SIGINFO != 0 {
+            let handler: extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void) =
+                std::mem::transmute(handler);
+            handler(signum, info, context);
+        } else {
+            let handler: extern "C" fn(libc::c_int) = std::mem::transmute(handler);
+            handler(signum);
+        }
+    }
+}
+
+/// Whether the faulting address is just past the end of the given thread's stack, i.e. in its
+/// guard page.
+#[cfg(target_os = "linux")]
+fn is_stack_guard_fault(thread: libc::pthread_t, fault: usize) -> bool {
+    let mut attr = std::mem::MaybeUninit::<libc::pthread_attr_t>::uninit();
+
+    // safety: attr is only read after being initialised by pthread_getattr_np, and is destroyed
+    // afterwards
+    let (stack_start, guard_size) = unsafe {
+        if libc::pthread_getattr_np(thread, attr.as_mut_ptr()) != 0 {
+            return false;
+        }
+
+        let mut stack_start = std::ptr::null_mut();
+        let mut stack_size = 0;
+        let mut guard_size = 0;
+        let ret = libc::pthread_attr_getstack(attr.as_ptr(), &mut stack_start, &mut stack_size)
+            | libc::pthread_attr_getguardsize(attr.as_ptr(), &mut guard_size);
+        libc::pthread_attr_destroy(attr.as_mut_ptr());
+
+        if ret != 0 {
+            return false;
+        }
+
+        (stack_start as usize, guard_size)
+    };
+
+    // some versions of glibc report the guard as part of the stack, so check either side
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(4096) as usize;
+    let guard_size = guard_size.max(page_size);
+    fault >= stack_start.saturating_sub(guard_size) && fault < stack_start + guard_size
+}
+
+#[cfg(target_os = "linux")]
+fn live_thread_count() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Threads:"))
+        .and_then(|count| count.trim().parse().ok())
+}
+
+#[cfg(windows)]
+fn live_thread_count() -> Option<usize> {
+    use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32,
+    };
+    use windows_sys::Win32::System::Threading::GetCurrentProcessId;
+
+    // safety: the snapshot handle is checked before use and closed afterwards, and entry has its
+    // size set as the API requires
+    unsafe {
+        // the snapshot includes every thread in the system, not just this process's
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return None;
+        }
+
+        let process = GetCurrentProcessId();
+        let mut entry = THREADENTRY32 {
+            dwSize: std::mem::size_of::<THREADENTRY32>() as u32,
+            ..Default::default()
+        };
+        let mut count = 0;
+        let mut more = Thread32First(snapshot, &mut entry) != 0;
+        while more {
+            if entry.th32OwnerProcessID == process {
+                count += 1;
+            }
+            more = Thread32Next(snapshot, &mut entry) != 0;
+        }
+        CloseHandle(snapshot);
+
+        Some(count).filter(|count| *count > 0)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn live_thread_count() -> Option<usize> {
+    None
+}
+
+/// Spawns a thread to resolve the backtraces of panics with the sequence numbers it receives, see
+/// [Builder::background_resolver]. It exits when the sender is dropped.
+fn spawn_background_resolver() -> std::io::Result<mpsc::Sender<u64>> {
+    let (tx, rx) = mpsc::channel::<u64>();
+
+    std::thread::Builder::new()
+        .name("panik-resolver".to_owned())
+        .spawn(move || {
+            for sequence in rx {
+                // evicted backtraces aren't captured, and must not be brought back
+                let (mut backtrace, mut spawn_backtrace) = {
+                    let state = state_mutex();
+                    match state.panics.iter().find(|p| p.sequence == sequence) {
+                        Some(panic) if !panic.backtrace_resolved && panic.backtrace_captured => {
+                            (panic.backtrace.clone(), panic.spawn_backtrace.clone())
+                        }
+                        _ => continue,
+                    }
+                };
+
+                // resolve without holding the lock
+                backtrace.resolve();
+                if let Some(spawn_backtrace) = &mut spawn_backtrace {
+                    spawn_backtrace.resolve();
+                }
+
+                let mut state = state_mutex();
+                if let Some(panic) = state.panics.iter_mut().find(|p| {
+                    p.sequence == sequence && !p.backtrace_resolved && p.backtrace_captured
+                }) {
+                    panic.set_resolved_backtrace(backtrace, spawn_backtrace);
+                }
+            }
+        })?;
+
+    Ok(tx)
+}
+
+/// Severity of a message sent to the OS logging facility, see [os_log].
+#[cfg(any(
+    all(feature = "use-syslog", unix),
+    all(feature = "use-eventlog", windows)
+))]
+#[derive(Clone, Copy)]
+enum OsLogLevel {
+    Error,
+    Crit,
+}
+
+/// The name panics are logged under in the OS logging facility.
+#[cfg(any(
+    all(feature = "use-syslog", unix),
+    all(feature = "use-eventlog", windows)
+))]
+fn os_log_process_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| {
+            exe.file_stem()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "panik".to_owned())
+}
+
+/// Writes to syslog, connecting on first use. Failures are ignored as there's nowhere left to
+/// report them.
+#[cfg(all(feature = "use-syslog", unix))]
+fn os_log(level: OsLogLevel, message: &str) {
+    type Syslog = syslog::Logger<syslog::LoggerBackend, syslog::Formatter3164>;
+    lazy_static::lazy_static! {
+        static ref SYSLOG: std::sync::Mutex<Option<Syslog>> = std::sync::Mutex::new(None);
+    }
+
+    let mut syslog = SYSLOG.lock().unwrap_or_else(PoisonError::into_inner);
+    if syslog.is_none() {
+        *syslog = syslog::unix(syslog::Formatter3164 {
+            facility: syslog::Facility::LOG_USER,
+            hostname: None,
+            process: os_log_process_name(),
+            pid: std::process::id(),
+        })
+        .ok();
+    }
+
+    if let Some(syslog) = syslog.as_mut() {
+        let _ = match level {
+            OsLogLevel::Error => syslog.err(message),
+            OsLogLevel::Crit => syslog.crit(message),
+        };
+    }
+}
+
+/// Writes to the Windows Event Log. Failures are ignored as there's nowhere left to report them.
+#[cfg(all(feature = "use-eventlog", windows))]
+fn os_log(level: OsLogLevel, message: &str) {
+    use windows_sys::Win32::System::EventLog::{
+        DeregisterEventSource, RegisterEventSourceW, ReportEventW, EVENTLOG_ERROR_TYPE,
+    };
+
+    // the event log has no level above error
+    let _ = level;
+
+    let wide = |s: &str| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let source = wide(&os_log_process_name());
+    let message = wide(message);
+    let strings = [message.as_ptr()];
+
+    // safety: all strings are nul terminated and outlive the calls
+    unsafe {
+        let handle = RegisterEventSourceW(std::ptr::null(), source.as_ptr());
+        if handle.is_null() {
+            return;
+        }
+
+        ReportEventW(
+            handle,
+            EVENTLOG_ERROR_TYPE,
+            0,
+            0,
+            std::ptr::null_mut(),
+            1,
+            0,
+            strings.as_ptr(),
+            std::ptr::null(),
+        );
+        DeregisterEventSource(handle);
+    }
+}
+
+fn spawn_watchdog(
+    max_duration: Duration,
+) -> std::io::Result<(mpsc::Sender<()>, std::thread::JoinHandle<()>)> {
+    let (tx, rx) = mpsc::channel::<()>();
+
+    let handle = std::thread::Builder::new()
+        .name("panik-watchdog".to_owned())
+        .spawn(move || {
+            if let Err(mpsc::RecvTimeoutError::Timeout) = rx.recv_timeout(max_duration) {
+                store_panic(Panic::on_current_thread(
+                    format!("scope exceeded max duration of {:?}", max_duration),
+                    None,
+                    None,
+                ));
+            }
+        })?;
+
+    Ok((tx, handle))
+}
+
+fn state_mutex() -> impl DerefMut<Target = State> {
+    #[cfg(feature = "use-parking-lot")]
+    return STATE.lock();
+
+    #[cfg(not(feature = "use-parking-lot"))]
+    STATE.lock().unwrap()
+}
+
+/// Identical to [run_and_handle_panics] except the return type doesn't need to be [Debug].
+///
+/// This only matters when logging a return value has been swallowed due to a different thread
+/// panicking.
+pub fn run_and_handle_panics_no_debug<R>(do_me: impl FnOnce() -> R + UnwindSafe) -> Option<R> {
+    run_and_handle_panics_with_maybe_debug(None, do_me, format_unprintable)
+}
+
+/// Identical to [run_and_handle_panics] except a swallowed return value is logged with the given
+/// formatter instead of [Debug], e.g. to redact or summarise it.
+///
+/// The formatter is only called when the closure returned successfully but a different thread
+/// panicked, so the return value has to be swallowed.
+pub fn run_and_handle_panics_with<R>(
+    do_me: impl FnOnce() -> R + UnwindSafe,
+    format_swallowed: impl FnOnce(R) -> Cow<'static, str>,
+) -> Option<R> {
+    run_and_handle_panics_with_maybe_debug(None, do_me, format_swallowed)
+}
+
+/// Runs the given closure, catching any panics that occur on **all threads** while in the scope of
+/// the closure.
+///
+/// See [Builder] for configuration, this uses the same defaults as [Builder::new].
+///
+/// The [Debug] bound on `R` is only for logging purposes (in the event a successful result is
+/// swallowed by a panic on another thread) - see [run_and_handle_panics_no_debug] for an
+/// unconstrained return value.
+///
+/// This function can be called multiple times **serially**, but cannot be nested (see
+/// [Builder::on_nested]).
+///
+/// # Return value
+/// If any thread(s) panicked, `None` is returned and the offending [Panic]s are available in
+/// [panics] and [has_panicked] until the next call to this function. Otherwise
+/// if no panics occur, `Some(R)` is returned.
+///
+/// # Example
+///
+/// See the `tests/` directory for more examples.
+///
+/// ```
+/// # fn main() {
+/// let result = panik::run_and_handle_panics(|| panic!("oh no"));
+/// assert!(result.is_none());
+/// assert!(panik::has_panicked());
+///
+/// let panics = panik::panics();
+/// assert_eq!(panics.len(), 1);
+///
+/// let panic = &panics[0];
+/// assert_eq!(panic.thread_id(), std::thread::current().id());
+/// assert_eq!(panic.message(), "oh no");
+/// # }
+/// ```
+pub fn run_and_handle_panics<R: Debug>(do_me: impl FnOnce() -> R + UnwindSafe) -> Option<R> {
+    run_and_handle_panics_with_maybe_debug(None, do_me, format_debug)
+}
+
+/// Repeatedly calls `tick` in a scope like [run_and_handle_panics], until either it returns
+/// [ControlFlow::Break] or a panic is detected with [has_panicked].
+///
+/// This encapsulates the common pattern of polling for panics in a game loop. The result is `None`
+/// if any thread panicked, otherwise the value `tick` broke with.
+///
+/// Unlike [run_and_handle_panics], `tick` doesn't need to be [UnwindSafe], so it can mutate
+/// state captured by reference. Any such state may be left inconsistent if `tick` itself panics.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # fn main() {
+/// let mut frame = 0;
+/// let result = panik::run_loop_until_panic(|| {
+///     frame += 1;
+///     if frame == 3 {
+///         let _ = std::thread::spawn(|| panic!("oh no")).join();
+///     }
+///
+///     ControlFlow::<()>::Continue(())
+/// });
+///
+/// assert!(result.is_none());
+/// assert_eq!(frame, 3);
+/// # }
+/// ```
+pub fn run_loop_until_panic<R>(mut tick: impl FnMut() -> ControlFlow<R>) -> Option<R> {
+    let do_me = AssertUnwindSafe(move || loop {
+        if has_panicked() {
+            return None;
+        }
+
+        if let ControlFlow::Break(res) = tick() {
+            return Some(res);
+        }
+    });
+
+    run_and_handle_panics_with_maybe_debug(None, do_me, format_unprintable).flatten()
+}
+
+/// Runs a block with [run_and_handle_panics] (or [Builder::run_and_handle_panics] if a builder is
+/// given), without needing the block to be [UnwindSafe].
+///
+/// The block is moved into a closure wrapped in [AssertUnwindSafe], so it must only use state
+/// that is still valid if it panics part way through. Use the functions directly for more control.
+///
+/// ```
+/// # fn main() {
+/// let numbers = vec![1, 2, 3];
+/// let result = panik::guarded!({ numbers.iter().sum::<i32>() });
+/// assert_eq!(result, Some(6));
+///
+/// let builder = panik::Builder::new().backtrace_resolution_limit(1);
+/// let result = panik::guarded!(builder, {
+///     let empty: Vec<i32> = Vec::new();
+///     empty[0]
+/// });
+/// assert!(result.is_none());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! guarded {
+    ($body:block) => {
+        $crate::run_and_handle_panics(::std::panic::AssertUnwindSafe(move || $body))
+    };
+    ($builder:expr, $body:block) => {
+        $crate::Builder::run_and_handle_panics(
+            $builder,
+            ::std::panic::AssertUnwindSafe(move || $body),
+        )
+    };
+}
+
+fn format_debug<R: Debug>(res: R) -> Cow<'static, str> {
+    Cow::Owned(format!("{:?}", res))
+}
+
+fn format_unprintable<R>(_: R) -> Cow<'static, str> {
+    Cow::Borrowed("<unprintable>")
+}
+
+/// Identical to [run_and_handle_panics] except any panic is fatal to the process, so they can't
+/// be accidentally ignored.
+///
+/// If any thread(s) panicked, the process exits with code 1 (or as chosen by
+/// [Builder::exit_code_classifier]) after running the action configured with
+/// [Builder::failure_action]. Otherwise the closure's result is returned.
+pub fn run_and_handle_panics_strict<R: Debug>(do_me: impl FnOnce() -> R + UnwindSafe) -> R {
+    run_strict(None, do_me)
+}
+
+/// Identical to [run_and_handle_panics] except panics are returned as a [PanicError], so they can
+/// be propagated with `?` like any other error.
+///
+/// ```
+/// # fn main() {
+/// fn run() -> Result<(), Box<dyn std::error::Error>> {
+///     panik::run_and_handle_panics_result(|| panic!("oh no"))?;
+///     Ok(())
+/// }
+///
+/// let err = run().unwrap_err();
+/// assert_eq!(err.to_string(), "1 threads panicked, first: oh no");
+/// # }
+/// ```
+pub fn run_and_handle_panics_result<R: Debug>(
+    do_me: impl FnOnce() -> R + UnwindSafe,
+) -> Result<R, PanicError> {
+    run_and_handle_panics_with_maybe_debug(None, do_me, format_debug)
+        .ok_or_else(|| PanicError(panics()))
+}
+
+fn run_strict<R: Debug>(builder: Option<&Builder>, do_me: impl FnOnce() -> R + UnwindSafe) -> R {
+    if let Some(res) = run_and_handle_panics_with_maybe_debug(builder, do_me, format_debug) {
+        return res;
+    }
+
+    let panics = panics();
+    log_error!(
+        &state_mutex(),
+        "exiting due to {count} panics",
+        count = panics.len()
+    );
+
+    if let Some(action) = builder.and_then(|b| b.failure_action.as_ref()) {
+        action(&panics);
+    }
+
+    let exit_code = builder
+        .and_then(|b| b.exit_code_classifier.as_ref())
+        .map_or(STRICT_EXIT_CODE, |classify| classify(&panics));
+    if let Some(exit_fn) = builder.and_then(|b| b.exit_fn.as_ref()) {
+        exit_fn(exit_code);
+    }
+    std::process::exit(exit_code)
+}
+
+fn run_and_handle_panics_with_maybe_debug<R>(
+    builder: Option<&Builder>,
+    do_me: impl FnOnce() -> R + UnwindSafe,
+    format_swallowed: impl FnOnce(R) -> Cow<'static, str>,
+) -> Option<R> {
+    let guard = match GlobalStateGuard::init(builder) {
+        Ok(guard) => guard,
+        Err(NestedPolicy::Transparent) => return Some(do_me()),
+        Err(_) => return None,
+    };
+
+    let result = std::panic::catch_unwind(do_me);
+
+    if result.is_ok() {
+        guard.wait_for_grace_period();
+    }
+
+    let mut state = state_mutex();
+    let result = result.map_err(|payload| {
+        if state.retain_payload {
+            state.retain_payload_of_current_thread(payload);
+        }
+    });
+
+    // saturating in case preserved panics were cleared during the scope
+    let new_panics = state.panics.len().saturating_sub(guard.preexisting_panics);
+
+    if let Some(describer) = builder.and_then(|b| b.thread_describer.as_ref()) {
+        for panic in state.panics.iter_mut().skip(guard.preexisting_panics) {
+            panic.thread_description = describer(panic.thread_id);
+        }
+    }
+
+    if let Some(classifier) = builder.and_then(|b| b.restart_classifier.as_ref()) {
+        for panic in state.panics.iter_mut().skip(guard.preexisting_panics) {
+            panic.restart_suggested = classifier(panic);
+        }
+    }
+
+    // formatted under the lock but logged after releasing it, as logging may block
+    let mut logs = Vec::new();
+
+    let early_return = match (result, new_panics) {
+        (Ok(res), 0) => {
+            // no panics
+            if builder.is_some_and(|b| b.log_clean_exit) {
+                logs.push(DeferredLog::Info(
+                    "scope completed with no panics".to_owned(),
+                ));
+            }
+
+            state.last_scope = Some(ScopeSummary::new(0, Severity::Clean));
+            Some(Some(res))
+        }
+        (Ok(res), _) if new_panics < state.soft_panic_threshold => {
+            logs.push(DeferredLog::Warn(format!(
+                "{count} threads panicked, tolerating as below the soft threshold of {threshold}",
+                count = new_panics,
+                threshold = state.soft_panic_threshold
+            )));
+            logs.extend(state.tolerated_panic_logs(guard.preexisting_panics));
+
+            state.last_scope = Some(ScopeSummary::new(new_panics, Severity::Soft));
+            Some(Some(res))
+        }
+        (Ok(res), _) if state.panic_budget.is_some() && !state.budget_exceeded => {
+            logs.push(DeferredLog::Warn(format!(
+                "{count} threads panicked, tolerating as within the panic budget",
+                count = new_panics,
+            )));
+            logs.extend(state.tolerated_panic_logs(guard.preexisting_panics));
+
+            state.last_scope = Some(ScopeSummary::new(new_panics, Severity::Soft));
+            Some(Some(res))
+        }
+        (Ok(res), _) => {
+            let swallowed = format_swallowed(res);
+
+            logs.push(DeferredLog::Warn(format!(
+                "panic occurred in another thread, swallowing unpanicked result: {}",
+                swallowed
+            )));
+            None
+        }
+        (Err(_), 0) => {
+            // the closure's panic wasn't stored, e.g. it was suppressed
+            logs.push(DeferredLog::Warn(
+                "closure panicked but the panic was not recorded, returning None".to_owned(),
+            ));
+            state.last_scope = Some(ScopeSummary::new(0, Severity::Fatal));
+            Some(None)
+        }
+        (Err(_), _) => None,
+    };
+
+    if let Some(res) = early_return {
+        let log_target = state.log_target();
+        drop(state);
+
+        for log in &logs {
+            log.log(&log_target);
+        }
+        return res;
+    }
+
+    state.last_scope = Some(ScopeSummary::new(new_panics, Severity::Fatal));
+
+    if let Some(suppressed) = state
+        .log_rate_limiter
+        .as_mut()
+        .map(LogRateLimiter::take_suppressed)
+        .filter(|n| *n > 0)
+    {
+        logs.push(DeferredLog::Warn(format!(
+            "suppressed logging of {} panics",
+            suppressed
+        )));
+    }
+
+    // silent verbosity reports the count on its own below
+    if state.verbosity != Verbosity::Silent {
+        logs.push(DeferredLog::Error(format!(
+            "{count} threads panicked",
+            count = new_panics
+        )));
+    }
+
+    let backtrace_resolution_limit = state.backtrace_resolution_limit;
+    let mut panics = std::mem::take(&mut state.panics);
+    debug_assert!(!panics.is_empty(), "panics vec should not be empty");
+
+    // resolve the scope thread's panics first as they're the most actionable, then in order
+    let first_new = guard.preexisting_panics.min(panics.len());
+    REPORTING.with(|reporting| reporting.set(true));
+    let resolved = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        let new_panics = &mut panics[first_new..];
+        let mut resolve_order = (0..new_panics.len())
+            .filter(|i| new_panics[*i].backtrace_captured)
+            .collect::<Vec<_>>();
+        resolve_order.sort_by_key(|i| {
+            let panic = &new_panics[*i];
+            (panic.thread_id != guard.thread, panic.sequence)
+        });
+
+        if resolve_order.len() > backtrace_resolution_limit {
+            logs.push(DeferredLog::Warn(format!(
+                "handling more than {limit} panics, no longer resolving backtraces",
+                limit = backtrace_resolution_limit
+            )));
+        }
+
+        let resolution_started = Instant::now();
+        for i in resolve_order.into_iter().take(backtrace_resolution_limit) {
+            if let Some(budget) = state.backtrace_resolution_time_budget {
+                if resolution_started.elapsed() >= budget {
+                    logs.push(DeferredLog::Warn(format!(
+                        "spent more than {:?} resolving backtraces, no longer resolving backtraces",
+                        budget
+                    )));
+                    break;
+                }
+            }
+
+            new_panics[i].resolve_backtrace();
+        }
+
+        match state.verbosity {
+            Verbosity::Silent => logs.push(DeferredLog::Crit(format!(
+                "{} threads panicked",
+                new_panics.len()
+            ))),
+            Verbosity::Summary => {
+                logs.extend(
+                    new_panics
+                        .iter()
+                        .map(|panic| DeferredLog::Crit(panic.summary_line())),
+                );
+            }
+            Verbosity::Full => {
+                logs.extend(
+                    new_panics
+                        .iter()
+                        .map(|panic| DeferredLog::Crit(panic.report().to_string())),
+                );
+            }
+        }
+    }));
+
+    #[cfg(feature = "use-metrics")]
+    {
+        // preexisting panics may have been cleared during the scope
+        let new_panics = &panics[first_new..];
+        let resolved = new_panics
+            .iter()
+            .filter(|panic| panic.backtrace_resolved)
+            .count();
+        let unresolved = new_panics.len() - resolved;
+        metrics::gauge!("panik.backtraces_resolved").set(resolved as f64);
+        metrics::gauge!("panik.backtraces_unresolved").set(unresolved as f64);
+    }
+
+    // put panics back, even if resolving them panicked
+    let empty = std::mem::replace(&mut state.panics, panics);
+    debug_assert!(empty.is_empty());
+    std::mem::forget(empty);
+
+    let log_target = state.log_target();
+    drop(state);
+
+    let logged = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        for log in &logs {
+            log.log(&log_target);
+        }
+    }));
+    REPORTING.with(|reporting| reporting.set(false));
+
+    if resolved.is_err() || logged.is_err() {
+        log_warn!(
+            &log_target,
+            "panicked while reporting panics, some may not have been logged"
+        );
+    }
+
+    None
+}
+
+/// Gets a copy of all panics that have occurred since the last call to [run_and_handle_panics].
+pub fn panics() -> Vec<Panic> {
+    let mut state = state_mutex();
+    state.panics_consumed = true;
+    state.panics.clone() // efficiency be damned we're dying
+}
+
+/// Gets a copy of all global panics (see [panics]) together with those of every [Registry] that
+/// currently has a thread registered under it, or had one registered when it exited, ordered by
+/// [Panic::sequence], e.g. for a single crash report at shutdown.
+///
+/// This doesn't drain anything, so the global panics and each registry are left untouched.
+pub fn merge_all_registries() -> Vec<Panic> {
+    let (mut merged, registries) = {
+        let mut state = state_mutex();
+        state.panics_consumed = true;
+
+        let exited = state
+            .exited_registries
+            .iter()
+            .filter_map(|registry| registry.upgrade().map(Registry));
+
+        let mut registries: Vec<Registry> = Vec::new();
+        for registry in state.registries.values().cloned().chain(exited) {
+            if !registries.iter().any(|r| Arc::ptr_eq(&r.0, &registry.0)) {
+                registries.push(registry);
+            }
+        }
+        (state.panics.clone(), registries)
+    };
+
+    for registry in &registries {
+        merged.extend(registry.panics());
+    }
+    merged.sort_by_key(|panic| panic.sequence);
+    merged
+}
+
+/// Takes all panics that have occurred since the last call to [run_and_handle_panics], leaving
+/// none stored.
+pub fn take_panics() -> Vec<Panic> {
+    let mut state = state_mutex();
+    state.panics_consumed = true;
+    let panics = std::mem::take(&mut state.panics);
+    state.recount_backtrace_bytes();
+    state.update_has_panicked();
+    panics
+}
+
+/// Gets a copy of all panics that have occurred since the last call to [run_and_handle_panics],
+/// with those sharing a [PanicKey] collapsed into one, e.g. for a storm of identical panics
+/// across a thread pool.
+///
+/// Each entry holds the first panic with its key, and they are ordered by when that first panic
+/// occurred.
+pub fn deduplicated_panics() -> Vec<DeduplicatedPanic> {
+    let mut state = state_mutex();
+    state.panics_consumed = true;
+
+    let mut deduplicated: Vec<DeduplicatedPanic> = Vec::new();
+    let mut indices: HashMap<PanicKey, usize> = HashMap::new();
+    for panic in &state.panics {
+        match indices.entry(panic.key()) {
+            Entry::Occupied(e) => deduplicated[*e.get()].count += 1,
+            Entry::Vacant(e) => {
+                e.insert(deduplicated.len());
+                deduplicated.push(DeduplicatedPanic {
+                    panic: panic.clone(),
+                    count: 1,
+                });
+            }
+        }
+    }
+    deduplicated
+}
+
+/// Takes a snapshot of all panics that have occurred since the last call to
+/// [run_and_handle_panics].
+///
+/// The internal lock is only held while copying, so unlike iterating under the lock, it's safe to
+/// call back into panik while iterating the snapshot.
+pub fn snapshot() -> PanicSnapshot {
+    let mut state = state_mutex();
+    state.panics_consumed = true;
+    PanicSnapshot(state.panics.clone().into())
+}
+
+/// Gets a copy of all panics that occurred after the last scope ended, if enabled with
+/// [Builder::capture_after_scope].
+pub fn post_scope_panics() -> Vec<Panic> {
+    state_mutex().post_scope_panics.clone()
+}
+
+/// Gets a copy of all error logs recorded as soft panics in the current or last scope, if enabled
+/// with [Builder::capture_error_logs].
+#[cfg(feature = "use-log")]
+pub fn soft_panics() -> Vec<Panic> {
+    state_mutex().soft_panics.clone()
+}
+
+/// Whether any panic has occurred since the last call to [run_and_handle_panics].
+///
+/// If a panic budget is configured with [Builder::panic_budget], this is only true once the
+/// budget has been exceeded.
+///
+/// This doesn't take panik's internal lock, so is cheap to poll and safe to call from a signal
+/// handler.
+pub fn has_panicked() -> bool {
+    HAS_PANICKED.load(std::sync::atomic::Ordering::Acquire)
+}
+
+/// Enters drain mode, signalling that workers should stop pulling new tasks and let in-flight work
+/// finish so the scope can end gracefully. This can be entered automatically once a scope fails
+/// with [Builder::drain_on_first_panic], and lasts until the next scope begins.
+///
+/// This is only advisory: nothing is stopped by panik, so worker loops must check
+/// [is_draining] themselves.
+///
+/// ```
+/// # fn main() {
+/// let builder = panik::Builder::new().drain_on_first_panic(true);
+/// let result = builder.run_and_handle_panics(|| {
+///     let worker = std::thread::spawn(|| {
+///         let mut tasks = 0;
+///         while !panik::is_draining() {
+///             tasks += 1;
+///             if tasks == 3 {
+///                 panic!("task failed");
+///             }
+///         }
+///     });
+///     let _ = worker.join();
+/// });
+///
+/// assert!(result.is_none());
+/// assert!(panik::is_draining());
+/// # }
+/// ```
+pub fn enter_drain_mode() {
+    DRAINING.store(true, std::sync::atomic::Ordering::Release);
+}
+
+/// Whether drain mode has been entered in the current scope, see [enter_drain_mode].
+///
+/// Like [has_panicked], this doesn't take panik's internal lock, so is cheap to poll.
+pub fn is_draining() -> bool {
+    DRAINING.load(std::sync::atomic::Ordering::Acquire)
+}
+
+/// Whether more panics than allowed by [Builder::panic_budget] occurred within its window in the
+/// most recent scope.
+pub fn budget_exceeded() -> bool {
+    state_mutex().budget_exceeded
+}
+
+/// The number of panics whose backtraces were dropped to stay within
+/// [Builder::max_total_backtrace_bytes] in the most recent scope.
+pub fn backtraces_evicted() -> usize {
+    state_mutex().backtraces_evicted
+}
+
+/// The number of panics ignored because of [Builder::capture_first_only] in the most recent
+/// scope.
+pub fn panics_dropped() -> usize {
+    state_mutex().panics_dropped
+}
+
+/// Whether the current thread is the one that started the currently running scope, e.g. so only
+/// that thread polls for panics. False if no scope is running.
+pub fn is_scope_owner_thread() -> bool {
+    state_mutex().scope_owner == Some(std::thread::current().id())
+}
+
+/// Gets a copy of the panic that occurred on the thread that started the current or most recent
+/// scope, if any, e.g. to tell whether the main loop itself died rather than a worker. This avoids
+/// copying all panics as with [panics].
+///
+/// If the scope owner somehow panicked more than once, the first panic is returned.
+pub fn owner_thread_panic() -> Option<Panic> {
+    let mut state = state_mutex();
+    state.panics_consumed = true;
+    let owner = state.last_scope_owner?;
+    state
+        .panics
+        .iter()
+        .find(|panic| panic.thread_id == owner)
+        .cloned()
+}
+
+/// Captures a backtrace of the current call site and attaches it to any panics later caught on
+/// this thread, available from [Panic::spawn_backtrace]. Thread pools should call this when
+/// handing a thread its first task, so panics can be traced back to where the worker came from.
+///
+/// Calling this again on the same thread replaces the previous spawn site.
+pub fn register_current_thread_spawn_site() {
+    let backtrace = Backtrace::new_unresolved();
+    let tid = std::thread::current().id();
+    forget_current_thread_on_exit(tid);
+    state_mutex().spawn_sites.insert(tid, backtrace);
+}
+
+/// How long the currently running scope has been running for, or `None` if no scope is running.
+pub fn scope_uptime() -> Option<Duration> {
+    state_mutex().scope_started.map(|started| started.elapsed())
+}
+
+/// Stops capturing panics in the current scope until [resume_capture] is called, e.g. to bracket
+/// a known-flaky section where panics shouldn't be fatal. This applies to all threads.
+///
+/// Panics while paused are not stored, so don't trip [has_panicked] or fail the scope unless they
+/// unwind out of the closure itself. Instead they're passed to the panic hook that was installed
+/// before the scope began, e.g. the default hook that prints them to stderr. Capture is resumed
+/// automatically when the scope ends.
+pub fn pause_capture() {
+    state_mutex().capture_paused = true;
+}
+
+/// Resumes capturing panics after [pause_capture].
+pub fn resume_capture() {
+    state_mutex().capture_paused = false;
+}
+
+/// Whether the given thread has panicked since the last call to [run_and_handle_panics].
+pub fn has_thread_panicked(tid: ThreadId) -> bool {
+    state_mutex().panics.iter().any(|p| p.thread_id == tid)
+}
+
+/// The IDs of all threads that have panicked since the last call to [run_and_handle_panics], in
+/// the order they first panicked and without duplicates, e.g. to tell which workers failed.
+pub fn panicked_thread_ids() -> Vec<ThreadId> {
+    let state = state_mutex();
+    let mut tids = Vec::new();
+    for panic in &state.panics {
+        if !tids.contains(&panic.thread_id) {
+            tids.push(panic.thread_id);
+        }
+    }
+    tids
+}
+
+/// Removes all stored panics that match the given predicate, keeping the rest. [has_panicked]
+/// and [panics] reflect only the remaining panics afterwards.
+///
+/// This is useful for acknowledging recoverable panics while keeping others for later reporting.
+/// The predicate is called while panik's internal lock is held, so must not call back into panik.
+pub fn clear_panics_matching(pred: impl Fn(&Panic) -> bool) {
+    let mut state = state_mutex();
+    state.panics.retain(|panic| !pred(panic));
+    state.recount_backtrace_bytes();
+    state.update_has_panicked();
+}
+
+/// Removes and returns all stored panics that match the given predicate, keeping the rest.
+/// [has_panicked] and [panics] reflect only the remaining panics afterwards.
+///
+/// This is the removing counterpart to [clear_panics_matching], e.g. for a triage workflow that
+/// handles some panics and leaves the rest pending. The predicate is called while panik's
+/// internal lock is held, so must not call back into panik.
+pub fn partition_panics(pred: impl Fn(&Panic) -> bool) -> Vec<Panic> {
+    let mut state = state_mutex();
+    state.panics_consumed = true;
+
+    let (matching, remaining) = std::mem::take(&mut state.panics)
+        .into_iter()
+        .partition(|panic| pred(panic));
+    state.panics = remaining;
+    state.recount_backtrace_bytes();
+    state.update_has_panicked();
+    matching
+}
+
+/// Records a panic with the given message on the current thread, without actually panicking.
+///
+/// This is useful for funnelling fatal errors that aren't real panics (e.g. a failed device
+/// initialisation) into the same reporting as real panics. No backtrace is captured.
+///
+/// ```
+/// # fn main() {
+/// let result = panik::run_and_handle_panics(|| {
+///     panik::record_synthetic_panic("no GPU found");
+///     assert!(panik::has_panicked());
+/// });
+/// assert!(result.is_none());
+/// assert_eq!(panik::panics()[0].message(), "no GPU found");
+/// # }
+/// ```
+#[track_caller]
+pub fn record_synthetic_panic(message: impl Into<String>) {
+    store_panic(Panic::on_current_thread(
+        message.into(),
+        Some(std::panic::Location::caller().into()),
+        None,
+    ));
+}
+
+/// Stores a panic as if it occurred on a thread with the given name, without actually panicking,
+/// to deterministically test code that queries or reports panics.
+///
+/// The panic goes through the same storage as a real one, so trips [has_panicked] and respects
+/// limits such as [Builder::panic_budget]. Its thread ID is that of the current thread, and no
+/// backtrace is captured.
+#[cfg(feature = "test-util")]
+#[track_caller]
+pub fn inject_panic(message: &str, thread_name: &str) {
+    let mut panic = Panic::on_current_thread(
+        message.to_owned(),
+        Some(std::panic::Location::caller().into()),
+        None,
+    );
+    panic.thread = format!("{} ({})", panic.thread_id_string, thread_name);
+    panic.thread_label = thread_name.to_owned();
+    store_panic(panic);
+}
+
+/// Labels the current thread for panic reporting, used in [Panic::thread_name] if the thread has
+/// no name of its own.
+///
+/// This is useful for thread pools that can't name their worker threads, but can label them.
+pub fn label_current_thread(label: impl Into<String>) {
+    let tid = std::thread::current().id();
+    forget_current_thread_on_exit(tid);
+    state_mutex().thread_labels.insert(tid, label.into());
+}
+
+/// Removes a thread's entries from [State]'s per-thread maps when it exits, so they don't grow
+/// without bound as pooled threads come and go.
+struct ForgetOnExit(Cell<Option<ThreadId>>);
+
+impl Drop for ForgetOnExit {
+    fn drop(&mut self) {
+        if let Some(thread) = self.0.get() {
+            let mut state = state_mutex();
+            state.thread_labels.remove(&thread);
+            state.spawn_sites.remove(&thread);
+            if let Some(registry) = state.registries.remove(&thread) {
+                state.remember_exited_registry(&registry);
+            }
+        }
+    }
+}
+
+thread_local! {
+    static FORGET_ON_EXIT: ForgetOnExit = const { ForgetOnExit(Cell::new(None)) };
+}
+
+fn forget_current_thread_on_exit(thread: ThreadId) {
+    // only fails if the thread is already exiting
+    let _ = FORGET_ON_EXIT.try_with(|forget| forget.0.set(Some(thread)));
+}
+
+/// Spawns a thread with the given name, also labelling it with [label_current_thread], so panics
+/// on it always have a useful [Panic::thread_name].
+///
+/// This is optional sugar for `std::thread::Builder::new().name(name).spawn(f)`, and likewise
+/// panics if the thread can't be spawned.
+pub fn spawn_labeled<F, T>(name: impl Into<String>, f: F) -> std::thread::JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let name = name.into();
+    std::thread::Builder::new()
+        .name(name.clone())
+        .spawn(move || {
+            label_current_thread(name);
+            f()
+        })
+        .expect("failed to spawn thread")
+}
+
+/// Renders all panics that have occurred since the last call to [run_and_handle_panics] into a
+/// single report, e.g. for displaying in a crash dialog.
+///
+/// Each panic is formatted the same as when it is logged at the end of the scope, including its
+/// backtrace if it has been resolved. Any environment variables captured with
+/// [Builder::capture_env_vars] are included in the header.
+pub fn render_report() -> String {
+    use std::fmt::Write;
+
+    let state = state_mutex();
+    let count = state.panics.len();
+
+    let mut report = format!("{} threads panicked\n", count);
+    if let Some(build_info) = &state.build_info {
+        let _ = writeln!(report, "build: {}", build_info);
+    }
+
+    for (name, value) in &state.env_snapshot {
+        let _ = match value {
+            Some(value) => writeln!(report, "env: {}={}", name, value),
+            None => writeln!(report, "env: {} (unset)", name),
+        };
+    }
+
+    for (i, panic) in state.panics.iter().enumerate() {
+        let _ = write!(report, "\n[{}/{}] {}\n", i + 1, count, panic.report());
+    }
+
+    report
+}
+
+/// Reads panics written to a file by [Builder::panic_log_file], possibly by another process.
+///
+/// Fails with [std::io::ErrorKind::InvalidData] if the file contains a malformed line.
+pub fn read_panics_from_file(path: impl AsRef<Path>) -> std::io::Result<Vec<PanicLite>> {
+    let contents = std::fs::read_to_string(path)?;
+    contents
+        .lines()
+        .map(|line| {
+            PanicLite::parse(line).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("malformed panic log line: {:?}", line),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Reads the panics recorded in a ring buffer file by [Builder::persistent_ring], possibly by a
+/// previous run of the process that crashed, oldest first.
+///
+/// Fails with [std::io::ErrorKind::InvalidData] if the file isn't a ring buffer. Records that are
+/// corrupt, e.g. torn by a crash mid-write, are skipped.
+pub fn read_persistent_ring(path: impl AsRef<Path>) -> std::io::Result<Vec<PanicLite>> {
+    let bytes = std::fs::read(path)?;
+    let capacity = parse_ring_header(&bytes).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, "not a panic ring buffer")
+    })?;
+
+    let mut records = bytes[RING_HEADER_SIZE..]
+        .chunks_exact(RING_RECORD_SIZE)
+        .take(capacity)
+        .filter_map(parse_ring_record)
+        .collect::<Vec<_>>();
+    records.sort_by_key(|(index, _)| *index);
+    Ok(records.into_iter().map(|(_, panic)| panic).collect())
+}
+
+/// Logs every stored panic again through the current logging backend, formatted the same as
+/// when logged at the end of a scope.
+///
+/// This is useful when the logger was initialised after early panics were captured, or to dump
+/// them again e.g. on a signal. The panics are not cleared.
+pub fn log_all_panics() {
+    // formatted under the lock but logged after releasing it, as a log sink may panic or block
+    let (reports, log_target) = {
+        let state = state_mutex();
+        let reports = state
+            .panics
+            .iter()
+            .map(|panic| panic.report().to_string())
+            .collect::<Vec<_>>();
+        (reports, state.log_target())
+    };
+
+    REPORTING.with(|reporting| reporting.set(true));
+    let logged = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        for report in &reports {
+            log_crit!(&log_target, "{}", report);
+        }
+    }));
+    REPORTING.with(|reporting| reporting.set(false));
+
+    if logged.is_err() {
+        log_warn!(
+            &log_target,
+            "panicked while logging panics, some may not have been logged"
+        );
+    }
+}
+
+/// The structured equivalent of [render_report], rendering all panics that have occurred since
+/// the last call to [run_and_handle_panics] into a single JSON document, e.g. for uploading to a
+/// crash reporting service.
+///
+/// The document has a `count`, a `panics` array, and a `diagnostics` object with the `build`
+/// info and captured `env` vars if either are configured. Unresolved backtraces are `null`.
+#[cfg(feature = "use-serde")]
+pub fn render_report_json() -> String {
+    use serde_json::json;
+
+    let state = state_mutex();
+
+    let panics = state
+        .panics
+        .iter()
+        .map(|panic| {
+            json!({
+                "sequence": panic.sequence,
+                "thread": panic.thread,
+                "message": panic.message,
+                "location": panic.location,
+                "backtrace": if panic.backtrace_resolved {
+                    Some(panic.backtrace_string())
+                } else {
+                    None
+                },
+                "breadcrumbs": panic.breadcrumbs,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut report = json!({
+        "count": panics.len(),
+        "panics": panics,
+    });
+
+    if state.build_info.is_some() || !state.env_snapshot.is_empty() {
+        let env = state
+            .env_snapshot
+            .iter()
+            .cloned()
+            .collect::<std::collections::BTreeMap<_, _>>();
+        report["diagnostics"] = json!({
+            "build": state.build_info,
+            "env": env,
+        });
+    }
+
+    report.to_string()
+}
+
+/// Subscribes to a stream of panics as they occur, from the point of subscribing onwards.
+///
+/// Each stream receives every panic, e.g. to trigger a graceful shutdown from an async
+/// select loop. The stream is backed by a bounded broadcast channel, so if a subscriber falls more
+/// than 64 panics behind (e.g. during a panic storm) it misses the oldest ones.
+#[cfg(feature = "use-tokio")]
+pub fn panic_stream() -> impl futures_util::Stream<Item = Panic> {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let rx = state_mutex().panic_sender.subscribe();
+    futures_util::stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(panic) => return Some((panic, rx)),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+/// Gets a summary of the most recent completed call to [run_and_handle_panics], or None if there
+/// hasn't been one.
+pub fn last_scope_summary() -> Option<ScopeSummary> {
+    state_mutex().last_scope.clone()
+}
+
+/// The number of attempts made by the most recent call to
+/// [Builder::run_and_handle_panics_with_retry], or 0 if there hasn't been one.
+pub fn last_attempt_count() -> usize {
+    state_mutex().last_attempt_count
+}
+
+/// Gets statistics for the most recent completed calls to [run_and_handle_panics], oldest first.
+///
+/// The number of scopes kept is configured with [Builder::scope_history_len].
+pub fn recent_scope_stats() -> Vec<ScopeStat> {
+    state_mutex().scope_history.iter().cloned().collect()
+}
+
+/// Whether the panic hook was verified to be working at the start of the most recent call to
+/// [run_and_handle_panics], and was still installed when it ended.
+///
+/// If this is false, panics are not being captured, e.g. because setting the panic hook is
+/// restricted on this platform or another library replaced it. Returns true if no scope has been
+/// run yet.
+///
+/// The hook is verified by raising and catching a panic, which is only done once per process.
+/// Every scope also checks that the hook wasn't replaced by the time it ends, so a library
+/// replacing it during a later scope is detected once that scope ends.
+pub fn is_capture_healthy() -> bool {
+    state_mutex().capture_healthy
+}
+
+impl Panic {
+    /// Describes the given panic as if it occurred on the current thread, with an unresolved
+    /// backtrace captured from here.
+    ///
+    /// This is what panik's own panic hook uses, and is useful for custom hooks that want to
+    /// produce [Panic]s compatible with panik's reporting.
+    pub fn from_panic_info(info: &PanicHookInfo) -> Panic {
+        let describer = state_mutex().panic_describer(std::thread::current().id());
+        Self::from_panic_info_with_backtrace(info, Some(Backtrace::new_unresolved()), &describer)
+    }
+
+    /// A None backtrace means capture was disabled.
+    fn from_panic_info_with_backtrace(
+        info: &PanicHookInfo,
+        backtrace: Option<Backtrace>,
+        describer: &PanicDescriber,
+    ) -> Panic {
+        // TODO use panic.message() when it stabilises
+        let (message, payload_type) = describe_payload(info.payload());
+        let message = message.unwrap_or_else(|| match payload_type {
+            Some(_) => Cow::from(format!("{}", info)),
+            None => match &describer.unknown_payload_message {
+                Some(describe) => Cow::from(describe(info)),
+                None => Cow::from(format!(
+                    "{} (unknown payload type {:?})",
+                    info,
+                    info.payload().type_id()
+                )),
+            },
+        });
+
+        // structured exceptions don't carry a rust payload
+        #[cfg(windows)]
+        let message = match payload_type {
+            Some(_) => message,
+            None => Cow::Owned(format!(
+                "{} (non-Rust panic payload; possible SEH)",
+                message
+            )),
+        };
+
+        let raw_message = match info.payload().downcast_ref::<&str>() {
+            Some(s) => Some(*s),
+            None => info.payload().downcast_ref::<String>().map(String::as_str),
+        };
+        let raw_message = match raw_message {
+            None => RawMessage::NotString,
+            Some(raw) if raw == message => RawMessage::SameAsMessage,
+            Some(raw) => RawMessage::Different(raw.to_owned()),
+        };
+
+        let t = std::thread::current();
+        let mut panic = Self::on_thread(
+            t.id(),
+            t.name(),
+            current_thread_stack_size(),
+            describer,
+            message.into_owned(),
+            info.location().map(PanicLocation::from),
+            backtrace,
+        );
+        panic.payload_type = payload_type;
+        panic.raw_message = raw_message;
+        panic
+    }
+
+    fn on_current_thread(
+        message: String,
+        location: Option<PanicLocation>,
+        backtrace: Option<Backtrace>,
+    ) -> Panic {
+        let t = std::thread::current();
+        let describer = state_mutex().panic_describer(t.id());
+        Self::on_thread(
+            t.id(),
+            t.name(),
+            current_thread_stack_size(),
+            &describer,
+            message,
+            location,
+            backtrace,
+        )
+    }
+
+    fn on_thread(
+        thread_id: ThreadId,
+        thread_name: Option<&str>,
+        thread_stack_size: Option<usize>,
+        describer: &PanicDescriber,
+        message: String,
+        location: Option<PanicLocation>,
+        backtrace: Option<Backtrace>,
+    ) -> Panic {
+        let (thread, thread_label, thread_id_string, tid) = {
+            let name = match thread_name {
+                Some(name) => name.to_owned(),
+                None => describer
+                    .thread_label
+                    .clone()
+                    .unwrap_or_else(|| "<unnamed>".to_owned()),
+            };
+            let thread_id_string = match &describer.thread_id_formatter {
+                Some(formatter) => formatter(thread_id),
+                None => format!("{:?}", thread_id),
+            };
+            (
+                format!("{} ({})", thread_id_string, name),
+                name,
+                thread_id_string,
+                thread_id,
+            )
+        };
 
-    #[cfg(not(feature = "use-parking-lot"))]
-    STATE.lock().unwrap()
+        Panic {
+            sequence: 0,
+            epoch: 0,
+            message,
+            thread_id: tid,
+            thread_id_string,
+            thread,
+            thread_label,
+            thread_description: None,
+            restart_suggested: false,
+            message_truncated: false,
+            raw_message: RawMessage::SameAsMessage,
+            thread_stack_size,
+            thread_cpu_time: None,
+            live_thread_count: None,
+            payload_type: None,
+            backtrace_captured: backtrace.is_some(),
+            backtrace: backtrace.unwrap_or_else(|| Backtrace::from(Vec::new())),
+            backtrace_resolved: false,
+            location,
+            payload: None,
+            breadcrumbs: Vec::new(),
+            backtrace_format: BacktraceFormat::Full,
+            origin_crate: None,
+            captured_by: None,
+            possibly_ffi: false,
+            spawn_backtrace: None,
+            context: HashMap::new(),
+        }
+    }
+
+    fn resolve_backtrace(&mut self) {
+        self.backtrace.resolve();
+        if let Some(spawn_backtrace) = &mut self.spawn_backtrace {
+            spawn_backtrace.resolve();
+        }
+        self.mark_backtrace_resolved();
+    }
+
+    /// Replaces the backtraces with ones resolved elsewhere, see [Builder::background_resolver].
+    fn set_resolved_backtrace(&mut self, backtrace: Backtrace, spawn_backtrace: Option<Backtrace>) {
+        self.backtrace = backtrace;
+        self.spawn_backtrace = spawn_backtrace;
+        self.mark_backtrace_resolved();
+    }
+
+    fn mark_backtrace_resolved(&mut self) {
+        self.backtrace_resolved = true;
+        self.origin_crate = origin_crate(&self.backtrace);
+    }
+
+    fn truncate_message(&mut self, max_len: usize) {
+        if self.message.len() <= max_len {
+            return;
+        }
+
+        let mut end = max_len;
+        while !self.message.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        match &mut self.raw_message {
+            RawMessage::SameAsMessage => self.raw_message = RawMessage::TruncatedMessage(end),
+            RawMessage::Different(raw) if raw.len() > max_len => {
+                let mut end = max_len;
+                while !raw.is_char_boundary(end) {
+                    end -= 1;
+                }
+                raw.truncate(end);
+            }
+            _ => {}
+        }
+        self.message.truncate(end);
+        self.message.push_str("…(truncated)");
+        self.message_truncated = true;
+    }
+
+    fn report(&self) -> PanicReport<'_> {
+        PanicReport(self)
+    }
+
+    /// The order this panic was captured in, unique and increasing across all panics captured in
+    /// this process. Zero if this panic was never stored by panik, e.g. if created with
+    /// [Panic::from_panic_info] in a custom hook.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// A process-local monotonic counter taken as soon as panik's hook sees this panic, before
+    /// it does anything slow like capturing the backtrace. Unlike timestamps, this can't be
+    /// affected by adjustments to the system clock, so gives a reliable ordering of when panics
+    /// occurred, whereas [Panic::sequence] is the order they were stored in.
+    ///
+    /// It is never reset, so is unique across scopes in this process but not across processes.
+    /// Zero if this panic didn't go through panik's hook, e.g. if created with
+    /// [Panic::from_panic_info] in a custom hook.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Whether the backtrace for this panic has been resolved.
+    pub fn is_backtrace_resolved(&self) -> bool {
+        self.backtrace_resolved
+    }
+
+    /// Whether any frame in the resolved backtrace has a symbol name containing the given
+    /// string, e.g. the name of the function expected to have panicked. Always false if the
+    /// backtrace hasn't been resolved (see [Panic::is_backtrace_resolved]).
+    pub fn backtrace_contains_symbol(&self, name: &str) -> bool {
+        self.backtrace_resolved
+            && self.backtrace.frames().iter().any(|frame| {
+                frame.symbols().iter().any(|symbol| {
+                    symbol
+                        .name()
+                        .is_some_and(|symbol| symbol.to_string().contains(name))
+                })
+            })
+    }
+
+    /// The panic message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The first line of the panic message, e.g. without the values of a failed `assert_eq!`.
+    pub fn short_message(&self) -> &str {
+        self.message.lines().next().unwrap_or("")
+    }
+
+    /// Whether the message was truncated, see [Builder::max_message_len].
+    pub fn message_truncated(&self) -> bool {
+        self.message_truncated
+    }
+
+    /// The thread that this panic occurred on.
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    /// A string describing the thread e.g. "ThreadId(12) (worker-thread)".
+    pub fn thread_name(&self) -> &str {
+        &self.thread
+    }
+
+    /// A description of the thread from [Builder::thread_describer], or [Panic::thread_name] if
+    /// there was none.
+    pub fn thread_description(&self) -> &str {
+        self.thread_description.as_deref().unwrap_or(&self.thread)
+    }
+
+    /// Whether the subsystem this panic occurred in should be restarted, as decided by
+    /// [Builder::restart_classifier]. Defaults to false.
+    pub fn restart_suggested(&self) -> bool {
+        self.restart_suggested
+    }
+
+    /// The type name of the original panic payload, if it was one of the recognised types: `&str`,
+    /// `String`, `i32`, `u32`, `i64` or `u64`. Numeric payloads (e.g. from
+    /// `std::panic::panic_any(42)`) are rendered as the message.
+    pub fn payload_type_name(&self) -> Option<&str> {
+        self.payload_type
+    }
+
+    /// The number of threads alive in the process when this panic occurred, if enabled with
+    /// [Builder::capture_live_thread_count] and the platform permits retrieving it. This is only
+    /// supported on Linux and Windows. Useful for telling a single worker dying apart from a whole
+    /// thread pool collapsing.
+    pub fn live_thread_count(&self) -> Option<usize> {
+        self.live_thread_count
+    }
+
+    /// The configured stack size of the thread this panic occurred on, if the platform permits
+    /// retrieving it. This is only supported on Linux, and for the main thread is the stack size
+    /// limit rather than the exact size. Useful for telling resource exhaustion apart from logic
+    /// errors.
+    pub fn thread_stack_size(&self) -> Option<usize> {
+        self.thread_stack_size
+    }
+
+    /// How much CPU time the thread this panic occurred on had consumed when it panicked, if
+    /// enabled with [Builder::capture_cpu_time]. This is only supported on Linux.
+    pub fn thread_cpu_time(&self) -> Option<Duration> {
+        self.thread_cpu_time
+    }
+
+    /// A single line summary of this panic in the form
+    /// `thread <name> (<id>) panicked: <message> at <location>`, never including the backtrace.
+    /// Any newlines in the message are escaped.
+    pub fn summary_line(&self) -> String {
+        let mut line = format!(
+            "thread {} ({}) panicked: {}",
+            self.thread_label,
+            self.thread_id_string,
+            self.message.replace('\r', "\\r").replace('\n', "\\n")
+        );
+
+        if let Some(location) = &self.location {
+            line.push_str(&format!(" at {}", location));
+        }
+
+        line
+    }
+
+    /// The source location of this panic, if known.
+    pub fn location(&self) -> Option<&PanicLocation> {
+        self.location.as_ref()
+    }
+
+    /// The components of this panic as captured, before panik's formatting, e.g. the message
+    /// without the `panicked at` prefix added for `String` payloads. This is for custom reporters
+    /// that want to make their own formatting decisions, and doesn't affect [Panic::message] or
+    /// [Panic::thread_name].
+    ///
+    /// [Builder::max_message_len] still applies to the raw message so memory stays bounded, but
+    /// without the `"…(truncated)"` suffix.
+    pub fn raw(&self) -> PanicRaw<'_> {
+        PanicRaw {
+            message: match &self.raw_message {
+                RawMessage::NotString => None,
+                RawMessage::SameAsMessage => Some(&self.message),
+                RawMessage::TruncatedMessage(len) => Some(&self.message[..*len]),
+                RawMessage::Different(message) => Some(message),
+            },
+            location: self
+                .location
+                .as_ref()
+                .map(|location| (location.file.as_str(), location.line, location.column)),
+            thread_id: self.thread_id,
+        }
+    }
+
+    /// The key identifying the bug this panic was caused by, equal for panics with the same
+    /// message and source location regardless of the thread they occurred on.
+    pub fn key(&self) -> PanicKey {
+        PanicKey {
+            message: self.message.clone(),
+            location: self.location.clone(),
+        }
+    }
+
+    /// The backtrace for this panic.
+    ///
+    /// This is an empty placeholder if no backtrace was captured, e.g. for synthetic panics or
+    /// with [Builder::capture_backtraces] disabled. Prefer [Panic::backtrace_opt] to tell the
+    /// difference.
+    pub fn backtrace(&self) -> &Backtrace {
+        &self.backtrace
+    }
+
+    /// Clones this panic without its backtrace, which is much cheaper when only the metadata is
+    /// needed. The clone has an empty, unresolved backtrace.
+    pub fn clone_without_backtrace(&self) -> Panic {
+        Panic {
+            sequence: self.sequence,
+            epoch: self.epoch,
+            message: self.message.clone(),
+            thread_id: self.thread_id,
+            thread_id_string: self.thread_id_string.clone(),
+            thread: self.thread.clone(),
+            thread_label: self.thread_label.clone(),
+            thread_description: self.thread_description.clone(),
+            restart_suggested: self.restart_suggested,
+            message_truncated: self.message_truncated,
+            raw_message: self.raw_message.clone(),
+            thread_stack_size: self.thread_stack_size,
+            thread_cpu_time: self.thread_cpu_time,
+            live_thread_count: self.live_thread_count,
+            payload_type: self.payload_type,
+            backtrace: Backtrace::from(Vec::new()),
+            backtrace_captured: false,
+            backtrace_resolved: false,
+            location: self.location.clone(),
+            payload: self.payload.clone(),
+            breadcrumbs: self.breadcrumbs.clone(),
+            backtrace_format: self.backtrace_format,
+            origin_crate: self.origin_crate.clone(),
+            captured_by: self.captured_by,
+            possibly_ffi: self.possibly_ffi,
+            spawn_backtrace: None,
+            context: self.context.clone(),
+        }
+    }
+
+    /// Annotates this panic with a key/value pair of application-specific context, replacing any
+    /// existing value for the key. See [Panic::context_map].
+    ///
+    /// This only affects this copy of the panic, not those stored by panik.
+    pub fn with_context(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.context.insert(key.into(), value.into());
+        self
+    }
+
+    /// The context this panic has been annotated with by [Panic::with_context].
+    pub fn context_map(&self) -> &HashMap<String, String> {
+        &self.context
+    }
+
+    /// The backtrace of where this panic's thread was spawned, if it was registered with
+    /// [register_current_thread_spawn_site]. Resolved along with [Panic::backtrace].
+    pub fn spawn_backtrace(&self) -> Option<&Backtrace> {
+        self.spawn_backtrace.as_ref()
+    }
+
+    /// The backtrace for this panic, or `None` if no backtrace was captured.
+    pub fn backtrace_opt(&self) -> Option<&Backtrace> {
+        if self.backtrace_captured {
+            Some(&self.backtrace)
+        } else {
+            None
+        }
+    }
+
+    /// The backtrace for this panic rendered according to [Builder::backtrace_format], or an
+    /// empty string if it hasn't been resolved.
+    pub fn backtrace_string(&self) -> String {
+        if !self.backtrace_resolved {
+            return String::new();
+        }
+
+        match self.backtrace_format {
+            BacktraceFormat::Full => format!("{:?}", self.backtrace),
+            BacktraceFormat::Short => {
+                let frames = self
+                    .backtrace
+                    .frames()
+                    .iter()
+                    .filter(|frame| !is_runtime_frame(frame))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                format!("{:?}", Backtrace::from(frames))
+            }
+        }
+    }
+
+    /// A heuristic guess at whether this panic unwound from an FFI callback, e.g. C code calling
+    /// back into a panicking Rust closure, based on its backtrace having suspiciously few frames.
+    ///
+    /// This is only a hint for triage when a backtrace looks wrong, and is always false if no
+    /// backtrace was captured or it was captured by [Builder::backtrace_capturer].
+    pub fn possibly_ffi(&self) -> bool {
+        self.possibly_ffi
+    }
+
+    /// The name of the scope that captured this panic, if set with [Builder::scope_name].
+    pub fn captured_by(&self) -> Option<&str> {
+        self.captured_by
+    }
+
+    /// A best-effort guess at the crate this panic originated from, based on the first frame in
+    /// the backtrace outside of the standard library and runtime.
+    ///
+    /// None if the backtrace hasn't been resolved or the crate can't be determined.
+    pub fn origin_crate(&self) -> Option<&str> {
+        self.origin_crate.as_deref()
+    }
+
+    /// Breadcrumbs captured at the time of the panic, see [Builder::breadcrumb_fn].
+    pub fn breadcrumbs(&self) -> &[String] {
+        &self.breadcrumbs
+    }
+
+    /// Gets a clone of the original panic payload if it is of type `T`, e.g. as passed to
+    /// [std::panic::panic_any].
+    ///
+    /// The payload is only retained if enabled with [Builder::retain_payload], and only for a
+    /// panic in the closure passed to [run_and_handle_panics] itself. Panics on other threads are
+    /// only borrowed by the panic hook, and their payloads are instead returned to whoever joins
+    /// the thread.
+    ///
+    /// The payload is shared between all clones of this panic, and so is cloned out rather than
+    /// borrowed. See [Panic::with_payload] for payloads that aren't `Clone`.
+    pub fn downcast_payload<T: Any + Clone>(&self) -> Option<T> {
+        self.with_payload(T::clone)
+    }
+
+    /// Calls `f` with a reference to the original panic payload if it is of type `T`, returning
+    /// its result. Like [Panic::downcast_payload] but for payloads that can't be cloned.
+    ///
+    /// The payload is locked while `f` runs, so `f` must not access the payload of this panic or
+    /// any of its clones.
+    pub fn with_payload<T: Any, R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+        let payload = self.payload.as_ref()?;
+        let payload = payload.lock().unwrap_or_else(PoisonError::into_inner);
+        payload.downcast_ref::<T>().map(f)
+    }
 }
 
-/// Identical to [run_and_handle_panics] except the return type doesn't need to be [Debug].
-///
-/// This only matters when logging a return value has been swallowed due to a different thread
-/// panicking.
-pub fn run_and_handle_panics_no_debug<R>(do_me: impl FnOnce() -> R + UnwindSafe) -> Option<R> {
-    run_and_handle_panics_with_maybe_debug(do_me, |_| Cow::Borrowed("<unprintable>"))
+impl ScopeSummary {
+    fn new(panic_count: usize, severity: Severity) -> Self {
+        ScopeSummary {
+            panic_count,
+            severity,
+        }
+    }
+
+    /// The number of panics that occurred in the scope.
+    pub fn panic_count(&self) -> usize {
+        self.panic_count
+    }
+
+    /// Whether the panics were tolerated or fatal.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
 }
 
-/// Runs the given closure, catching any panics that occur on **all threads** while in the scope of
-/// the closure.
-///
-/// See [Builder] for configuration.
-///
-/// The [Debug] bound on `R` is only for logging purposes (in the event a successful result is
-/// swallowed by a panic on another thread) - see [run_and_handle_panics_no_debug] for an
-/// unconstrained return value.
-///
-/// This function can be called multiple times **serially**, but cannot be nested.
-///
-/// # Return value
-/// If any thread(s) panicked, `None` is returned and the offending [Panic]s are available in
-/// [panics] and [has_panicked] until the next call to this function. Otherwise
-/// if no panics occur, `Some(R)` is returned.
-///
-/// # Example
-///
-/// See the `tests/` directory for more examples.
-///
-/// ```
-/// # fn main() {
-/// let result = panik::run_and_handle_panics(|| panic!("oh no"));
-/// assert!(result.is_none());
-/// assert!(panik::has_panicked());
-///
-/// let panics = panik::panics();
-/// assert_eq!(panics.len(), 1);
-///
-/// let panic = &panics[0];
-/// assert_eq!(panic.thread_id(), std::thread::current().id());
-/// assert_eq!(panic.message(), "oh no");
-/// # }
-/// ```
-pub fn run_and_handle_panics<R: Debug>(do_me: impl FnOnce() -> R + UnwindSafe) -> Option<R> {
-    run_and_handle_panics_with_maybe_debug(do_me, |res| Cow::Owned(format!("{:?}", res)))
+impl PanicLocation {
+    /// The source file the panic occurred in.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The line the panic occurred on.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The column the panic occurred at.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+impl From<&std::panic::Location<'_>> for PanicLocation {
+    fn from(location: &std::panic::Location) -> Self {
+        PanicLocation {
+            file: location.file().to_owned(),
+            line: location.line(),
+            column: location.column(),
+        }
+    }
+}
+
+impl PartialEq for Panic {
+    fn eq(&self, other: &Self) -> bool {
+        self.sequence == other.sequence
+    }
+}
+
+impl Eq for Panic {}
+
+impl PartialOrd for Panic {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Panic {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sequence.cmp(&other.sequence)
+    }
+}
+
+impl<'a> PanicRaw<'a> {
+    /// The message carried by the panic's payload, if it was a `&str` or `String`.
+    pub fn message(&self) -> Option<&'a str> {
+        self.message
+    }
+
+    /// The file, line and column the panic occurred at, if known.
+    pub fn location(&self) -> Option<(&'a str, u32, u32)> {
+        self.location
+    }
+
+    /// The ID of the thread the panic occurred on.
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+}
+
+impl PanicKey {
+    /// The panic message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The source location of the panic, if known.
+    pub fn location(&self) -> Option<&PanicLocation> {
+        self.location.as_ref()
+    }
+}
+
+impl DeduplicatedPanic {
+    /// The first panic with this key.
+    pub fn panic(&self) -> &Panic {
+        &self.panic
+    }
+
+    /// The number of panics with this key, including the first.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl std::ops::Deref for PanicSnapshot {
+    type Target = [Panic];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a> IntoIterator for &'a PanicSnapshot {
+    type Item = &'a Panic;
+    type IntoIter = std::slice::Iter<'a, Panic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for PanicSnapshot {
+    type Item = Panic;
+    type IntoIter = std::vec::IntoIter<Panic>;
+
+    /// Clones the panics out of the snapshot, as it may be shared.
+    fn into_iter(self) -> Self::IntoIter {
+        Vec::from(&self.0[..]).into_iter()
+    }
+}
+
+impl Display for PanicError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "scope panicked, but no panics were stored");
+        }
+
+        write!(f, "{} threads panicked", self.0.len())?;
+
+        if let Some(first) = self.0.first() {
+            write!(f, ", first: {}", first.message)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for PanicError {}
+
+impl Display for PanicLocation {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+impl Display for BuildInfo {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}, built {})",
+            self.version, self.git_sha, self.build_timestamp
+        )
+    }
+}
+
+impl Display for PanicReport<'_> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let panic = self.0;
+        write!(
+            f,
+            "panic on thread {:?}: {:?}",
+            panic.thread_description(),
+            panic.message
+        )?;
+
+        if let Some(location) = &panic.location {
+            write!(f, " at {}", location)?;
+        }
+
+        // dont log empty backtrace
+        if panic.backtrace_resolved {
+            write!(f, "\n{}", panic.backtrace_string())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl PanicError {
+    /// The panics that caused the scope to fail. This may be empty, see [PanicError].
+    pub fn panics(&self) -> &[Panic] {
+        &self.0
+    }
+
+    /// Takes the panics that caused the scope to fail.
+    pub fn into_panics(self) -> Vec<Panic> {
+        self.0
+    }
+}
+
+impl Registry {
+    /// Creates a new empty registry with the given name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Registry(Arc::new(RegistryInner {
+            name: name.into(),
+            panics: std::sync::Mutex::new(Vec::new()),
+        }))
+    }
+
+    fn panics_mutex(&self) -> impl DerefMut<Target = Vec<Panic>> + '_ {
+        self.0.panics.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The name of this registry.
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// Routes future panics on the given thread to this registry, replacing any registry it was
+    /// previously registered under.
+    ///
+    /// The thread is unregistered automatically when it exits only if it registered itself, e.g.
+    /// with [Registry::register_current_thread]. Otherwise call [Registry::unregister_thread]
+    /// once it's done.
+    pub fn register_thread(&self, tid: ThreadId) {
+        if tid == std::thread::current().id() {
+            forget_current_thread_on_exit(tid);
+        }
+        state_mutex().registries.insert(tid, self.clone());
+    }
+
+    /// Routes future panics on the current thread to this registry, see
+    /// [Registry::register_thread].
+    pub fn register_current_thread(&self) {
+        self.register_thread(std::thread::current().id());
+    }
+
+    /// Stops routing panics on the given thread to this registry, if it was registered under it.
+    pub fn unregister_thread(&self, tid: ThreadId) {
+        let mut state = state_mutex();
+        if let Some(registry) = state.registries.get(&tid) {
+            if Arc::ptr_eq(&registry.0, &self.0) {
+                state.registries.remove(&tid);
+            }
+        }
+    }
+
+    /// Gets a copy of all panics that have occurred on threads registered under this registry.
+    pub fn panics(&self) -> Vec<Panic> {
+        self.panics_mutex().clone()
+    }
+
+    /// Whether any panic has occurred on a thread registered under this registry.
+    pub fn has_panicked(&self) -> bool {
+        !self.panics_mutex().is_empty()
+    }
 }
 
-fn run_and_handle_panics_with_maybe_debug<R>(
-    do_me: impl FnOnce() -> R + UnwindSafe,
-    format_swallowed: impl FnOnce(R) -> Cow<'static, str>,
-) -> Option<R> {
-    let _guard = GlobalStateGuard::init();
+#[cfg(feature = "use-log")]
+impl<L: log::Log> ErrorLogCapture<L> {
+    /// Wraps the given logger.
+    pub fn new(inner: L) -> Self {
+        ErrorLogCapture(inner)
+    }
 
-    let result = std::panic::catch_unwind(|| do_me());
+    /// The wrapped logger.
+    pub fn inner(&self) -> &L {
+        &self.0
+    }
+}
 
-    let mut state = state_mutex();
-    match (result, state.panics.is_empty()) {
-        (Ok(res), true) => {
-            // no panics
-            return Some(res);
+#[cfg(feature = "use-log")]
+impl<L: log::Log> log::Log for ErrorLogCapture<L> {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() == log::Level::Error || self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        // panik logs errors while holding its own lock
+        let from_panik = record.target() == "panik" || record.target().starts_with("panik::");
+        if record.level() == log::Level::Error && !from_panik {
+            register_soft_panic(record);
         }
-        (Ok(res), false) => {
-            let swallowed = format_swallowed(res);
 
-            log_warn!(
-                &state,
-                "panic occurred in another thread, swallowing unpanicked result: {}",
-                swallowed
-            );
+        if self.0.enabled(record.metadata()) {
+            self.0.log(record);
         }
-        (Err(_), false) => {}
-        (Err(_), true) => unreachable!(),
-    };
+    }
 
-    log_error!(
-        &state,
-        "{count} threads panicked",
-        count = state.panics.len()
-    );
+    fn flush(&self) {
+        self.0.flush();
+    }
+}
 
-    let backtrace_resolution_limit = state.backtrace_resolution_limit;
-    let mut panics = std::mem::take(&mut state.panics);
-    debug_assert!(!panics.is_empty(), "panics vec should not be empty");
+impl PanicSemaphore {
+    /// Creates a new semaphore with no permits.
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    for (
-        i,
-        Panic {
-            message,
-            thread,
-            ref mut backtrace,
-            backtrace_resolved,
-            ..
-        },
-    ) in panics.iter_mut().enumerate()
-    {
-        match i.cmp(&backtrace_resolution_limit) {
-            Ordering::Less => {
-                backtrace.resolve();
-                *backtrace_resolved = true;
-            }
-            Ordering::Equal => {
-                #[cfg(feature = "use-log")]
-                log::warn!(
-                    "handling more than {limit} panics, no longer resolving backtraces",
-                    limit = backtrace_resolution_limit
-                );
+    fn permits(&self) -> std::sync::MutexGuard<'_, usize> {
+        self.0
+            .permits
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
 
-                #[cfg(feature = "use-stderr")]
-                eprintln!(
-                    "handling more than {limit} panics, no longer resolving backtraces",
-                    limit = backtrace_resolution_limit
-                );
-            }
-            _ => {}
-        };
+    fn release(&self) {
+        *self.permits() += 1;
+        self.0.released.notify_one();
+    }
 
-        if *backtrace_resolved {
-            log_crit!(
-                &state,
-                "panic on thread {:?}: {:?}\n{:?}",
-                thread,
-                message,
-                backtrace
-            );
-        } else {
-            // dont log empty backtrace
-            log_crit!(&state, "panic on thread {:?}: {:?}", thread, message,);
+    /// Blocks until a panic has released a permit, then takes it.
+    pub fn acquire(&self) {
+        let mut permits = self
+            .0
+            .released
+            .wait_while(self.permits(), |permits| *permits == 0)
+            .unwrap_or_else(PoisonError::into_inner);
+        *permits -= 1;
+    }
+
+    /// Blocks until a panic has released a permit or the timeout elapses, returning whether a
+    /// permit was taken.
+    pub fn acquire_timeout(&self, timeout: Duration) -> bool {
+        let (mut permits, _) = self
+            .0
+            .released
+            .wait_timeout_while(self.permits(), timeout, |permits| *permits == 0)
+            .unwrap_or_else(PoisonError::into_inner);
+        if *permits == 0 {
+            return false;
         }
+
+        *permits -= 1;
+        true
     }
 
-    // put panics back
-    let empty = std::mem::replace(&mut state.panics, panics);
-    debug_assert!(empty.is_empty());
-    std::mem::forget(empty);
+    /// Takes a permit if one is available without blocking, returning whether one was taken.
+    pub fn try_acquire(&self) -> bool {
+        let mut permits = self.permits();
+        if *permits == 0 {
+            return false;
+        }
 
-    None
-}
+        *permits -= 1;
+        true
+    }
 
-/// Gets a copy of all panics that have occurred since the last call to [run_and_handle_panics].
-pub fn panics() -> Vec<Panic> {
-    let state = state_mutex();
-    state.panics.clone() // efficiency be damned we're dying
+    /// The number of permits available, i.e. panics not yet acquired.
+    pub fn available_permits(&self) -> usize {
+        *self.permits()
+    }
 }
 
-/// Whether any panic has occurred since the last call to [run_and_handle_panics].
-pub fn has_panicked() -> bool {
-    !state_mutex().panics.is_empty()
+impl Suppression {
+    fn matches(&self, message: &str) -> bool {
+        match self {
+            Suppression::Substring(s) => message.contains(s.as_str()),
+            #[cfg(feature = "use-regex")]
+            Suppression::Regex(regex) => regex.is_match(message),
+        }
+    }
 }
 
-impl Panic {
-    /// Whether the backtrace for this panic has been resolved.
-    pub fn is_backtrace_resolved(&self) -> bool {
-        self.backtrace_resolved
+impl PanicLite {
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let mut next = || fields.next();
+
+        let process_id = next()?.parse().ok()?;
+        let sequence = next()?.parse().ok()?;
+        let thread = unescape_log_field(next()?)?;
+        let (file, line, column) = (next()?, next()?, next()?);
+        let message = unescape_log_field(next()?)?;
+
+        // absent from lines without a build or environment
+        let build_info = match next() {
+            Some(version) => Some(BuildInfo {
+                version: unescape_log_field(version)?,
+                git_sha: unescape_log_field(next()?)?,
+                build_timestamp: unescape_log_field(next()?)?,
+            }),
+            None => None,
+        }
+        .filter(|build_info| *build_info != BuildInfo::default());
+
+        let env = fields
+            .map(|field| match field.split_once('=') {
+                Some((name, value)) => {
+                    Some((unescape_log_field(name)?, Some(unescape_log_field(value)?)))
+                }
+                None => Some((unescape_log_field(field)?, None)),
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let location = if file.is_empty() {
+            None
+        } else {
+            Some(PanicLocation {
+                file: unescape_log_field(file)?,
+                line: line.parse().ok()?,
+                column: column.parse().ok()?,
+            })
+        };
+
+        Some(PanicLite {
+            process_id,
+            sequence,
+            thread,
+            message,
+            location,
+            build_info,
+            env,
+        })
+    }
+
+    /// The ID of the process that panicked.
+    pub fn process_id(&self) -> u32 {
+        self.process_id
+    }
+
+    /// The panic's [Panic::sequence] within the process that panicked.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// A string describing the thread, see [Panic::thread_name].
+    pub fn thread_name(&self) -> &str {
+        &self.thread
     }
 
     /// The panic message.
@@ -424,53 +5293,512 @@ impl Panic {
         &self.message
     }
 
-    /// The thread that this panic occurred on.
-    pub fn thread_id(&self) -> ThreadId {
-        self.thread_id
+    /// The source location of this panic, if known.
+    pub fn location(&self) -> Option<&PanicLocation> {
+        self.location.as_ref()
     }
 
-    /// A string describing the thread e.g. "ThreadId(12) (worker-thread)".
-    pub fn thread_name(&self) -> &str {
-        &self.thread
+    /// The build information set with [Builder::build_info] in the process that panicked, if any.
+    pub fn build_info(&self) -> Option<&BuildInfo> {
+        self.build_info.as_ref()
     }
 
-    /// The backtrace for this panic.
-    pub fn backtrace(&self) -> &Backtrace {
-        &self.backtrace
+    /// The environment variables captured by [Builder::capture_env_vars] in the process that
+    /// panicked, as `(name, value)` with `None` if unset. Always empty when read from a
+    /// [Builder::persistent_ring].
+    pub fn env_vars(&self) -> &[(String, Option<String>)] {
+        &self.env
+    }
+}
+
+impl ScopeStat {
+    /// The number of panics that occurred in the scope.
+    pub fn panic_count(&self) -> usize {
+        self.panic_count
+    }
+
+    /// The number of those panics that had their backtraces resolved.
+    pub fn resolved_count(&self) -> usize {
+        self.resolved_count
+    }
+
+    /// How long the scope ran for.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl LogRateLimiter {
+    fn new(max_per_sec: u32, now: Instant) -> Self {
+        LogRateLimiter {
+            max_per_sec,
+            window_start: now,
+            logged: 0,
+            suppressed: 0,
+        }
+    }
+
+    /// Returns whether a panic occurring now should be logged, and the number of suppressed
+    /// panics to report if a new window has just started.
+    fn should_log(&mut self, now: Instant) -> (bool, usize) {
+        let mut suppressed = 0;
+        if now.duration_since(self.window_start) >= Duration::from_secs(1) {
+            suppressed = self.take_suppressed();
+            self.window_start = now;
+            self.logged = 0;
+        }
+
+        if self.logged < self.max_per_sec {
+            self.logged += 1;
+            (true, suppressed)
+        } else {
+            self.suppressed += 1;
+            (false, suppressed)
+        }
+    }
+
+    fn take_suppressed(&mut self) -> usize {
+        std::mem::take(&mut self.suppressed)
     }
 }
 
 impl GlobalStateGuard {
-    fn init() -> Self {
+    /// Starts a new scope with the given settings, or the defaults if None. Fails with the
+    /// configured policy if a scope is already running.
+    fn init(builder: Option<&Builder>) -> Result<Self, NestedPolicy> {
         let mut state = state_mutex();
 
         // prevent nesting
         if state.is_running {
-            drop(state); // avoid poisoning mutex
-            panic!("nested calls to panik::run_and_handle_panics are not supported")
+            let policy = builder.map(|b| b.nested_policy).unwrap_or_default();
+            if let NestedPolicy::Panic = policy {
+                drop(state); // avoid poisoning mutex
+                panic!("nested calls to panik::run_and_handle_panics are not supported")
+            }
+
+            return Err(policy);
+        }
+
+        if std::mem::take(&mut state.require_handled)
+            && !state.panics_consumed
+            && !state.panics.is_empty()
+        {
+            log_warn!(
+                &state,
+                "{} panics from the previous scope were never handled",
+                state.panics.len()
+            );
+        }
+
+        // not reset at the end of the scope as it affects has_panicked
+        state.panic_budget = None;
+        state.budget_timestamps.clear();
+        state.budget_exceeded = false;
+        state.backtraces_evicted = 0;
+        state.first_panic_captured = false;
+        state.panics_dropped = 0;
+
+        // not reset at the end of the scope as it's included in reports rendered afterwards
+        state.env_snapshot.clear();
+
+        // builder-less scopes get the defaults too, e.g. those derived from RUST_BACKTRACE
+        match builder {
+            Some(builder) => builder.apply_settings(&mut state),
+            None => Builder::new().apply_settings(&mut state),
         }
-        state.panics.clear();
+        if !state.preserve_panics {
+            state.panics.clear();
+        }
+        state.recount_backtrace_bytes();
+        state.update_has_panicked();
+        DRAINING.store(false, std::sync::atomic::Ordering::Release);
+        state.post_scope_panics.clear();
+        #[cfg(feature = "use-log")]
+        state.soft_panics.clear();
         state.is_running = true;
+        state.scope_owner = Some(std::thread::current().id());
+        state.last_scope_owner = state.scope_owner;
+        state.scope_started = Some(Instant::now());
+        state.capture_paused = false;
+        let preexisting_panics = state.panics.len();
+        state.preexisting_panics = preexisting_panics;
 
-        std::panic::set_hook(Box::new(|panic| {
-            register_panic(panic);
-        }));
+        // kept to handle panics while capture is paused, and restored when the scope ends
+        let current_hook = std::panic::take_hook();
+        let previous_hook = match state.post_scope_hook.take() {
+            Some((address, wrapped)) if address == hook_address(&current_hook) => wrapped,
+            _ => Arc::new(current_hook),
+        };
+        let hook: PanicHook = Box::new({
+            let previous_hook = previous_hook.clone();
+            move |panic| {
+                if panic.payload().is::<HookSentinel>() {
+                    SENTINEL_CAUGHT.with(|caught| caught.set(true));
+                    return;
+                }
+
+                if REPORTING.with(Cell::get) {
+                    return;
+                }
+
+                if !register_panic(panic) {
+                    previous_hook(panic);
+                }
+            }
+        });
+        let hook_address = hook_address(&hook);
+        std::panic::set_hook(hook);
+
+        #[cfg(not(panic = "unwind"))]
+        {
+            static WARN_ABORT: std::sync::Once = std::sync::Once::new();
+            WARN_ABORT.call_once(|| {
+                log_warn!(
+                    &state,
+                    "compiled with panic = \"abort\", panics will be logged and then abort the process rather than being handled gracefully"
+                );
+            });
+        }
+
+        drop(state); // the sentinel panic must not poison the mutex
+        let healthy = Self::verify_hook();
+
+        let mut state = state_mutex();
+        if !healthy {
+            log_warn!(
+                &state,
+                "panic hook does not appear to be installed, panics will not be captured"
+            );
+        }
+        state.capture_healthy = healthy;
+
+        let watchdog = match state.max_scope_duration.map(spawn_watchdog) {
+            Some(Ok(watchdog)) => Some(watchdog),
+            Some(Err(err)) => {
+                log_warn!(&state, "failed to spawn scope watchdog thread: {}", err);
+                None
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            preexisting_panics,
+            thread: std::thread::current().id(),
+            started: Instant::now(),
+            watchdog,
+            hook_address,
+            previous_hook: Some(previous_hook),
+        })
+    }
+
+    /// Waits up to the configured grace period for a panic to be registered on another thread.
+    fn wait_for_grace_period(&self) {
+        let deadline = Instant::now() + state_mutex().grace_period;
+        loop {
+            if state_mutex().panics.len() != self.preexisting_panics {
+                break;
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+
+            std::thread::sleep(GRACE_PERIOD_POLL_INTERVAL.min(deadline - now));
+        }
+    }
 
-        Self
+    /// Raises and catches a sentinel panic to check it reaches our hook, in case setting the hook
+    /// was restricted or it was immediately replaced by someone else.
+    ///
+    /// This is only done by the first scope in the process, as unwinding is comparatively
+    /// expensive and trips debuggers that break on panics. Later scopes reuse the result, and
+    /// instead check the hook wasn't replaced when they end.
+    #[cfg(panic = "unwind")]
+    fn verify_hook() -> bool {
+        static VERIFIED: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+        *VERIFIED.get_or_init(|| {
+            SENTINEL_CAUGHT.with(|caught| caught.set(false));
+            let _ = std::panic::catch_unwind(|| std::panic::panic_any(HookSentinel));
+            SENTINEL_CAUGHT.with(|caught| caught.replace(false))
+        })
+    }
+
+    /// Panicking aborts the process so the hook can't be tested, assume the best.
+    #[cfg(not(panic = "unwind"))]
+    fn verify_hook() -> bool {
+        true
     }
 }
 
 impl Drop for GlobalStateGuard {
     fn drop(&mut self) {
-        let _ = std::panic::take_hook();
+        // before taking the lock, which the watchdog may be waiting on
+        if let Some((stop, watchdog)) = self.watchdog.take() {
+            drop(stop);
+            let _ = watchdog.join();
+        }
+
+        let hook = std::panic::take_hook();
+        let hook_replaced = hook_address(&hook) != self.hook_address;
+
+        // a replacement is left in place, otherwise the hook from before the scope is restored
+        let restored_hook = if hook_replaced {
+            hook
+        } else {
+            drop(hook);
+            match self.previous_hook.take().map(Arc::try_unwrap) {
+                Some(Ok(previous)) => previous,
+                Some(Err(shared)) => Box::new(move |panic: &PanicHookInfo| shared(panic)),
+                // unreachable as it's only taken here, but the default hook is a safe fallback
+                None => std::panic::take_hook(),
+            }
+        };
 
         let mut state = state_mutex();
+
+        if hook_replaced {
+            log_warn!(
+                &state,
+                "panic hook was replaced during the scope, panics may not have been captured"
+            );
+            state.capture_healthy = false;
+        }
+
+        state.abort_on_main_thread_panic = None;
+        state.exit_fn = None;
+
+        if std::mem::take(&mut state.capture_after_scope) {
+            let wrapped = Arc::new(restored_hook);
+            let hook: PanicHook = Box::new({
+                let wrapped = wrapped.clone();
+                move |panic| {
+                    register_post_scope_panic(panic);
+                    wrapped(panic);
+                }
+            });
+            state.post_scope_hook = Some((hook_address(&hook), wrapped));
+            std::panic::set_hook(hook);
+        } else {
+            std::panic::set_hook(restored_hook);
+        }
+
+        let new_panics = state.panics.get(self.preexisting_panics..).unwrap_or(&[]);
+        let stat = ScopeStat {
+            panic_count: new_panics.len(),
+            resolved_count: new_panics.iter().filter(|p| p.backtrace_resolved).count(),
+            duration: self.started.elapsed(),
+        };
+        state.record_scope_stat(stat);
+
         state.backtrace_resolution_limit = DEFAULT_BACKTRACE_RESOLUTION_LIMIT;
+        state.backtrace_resolution_time_budget = None;
+        state.preserve_panics = false;
+        state.grace_period = Duration::ZERO;
+        state.max_scope_duration = None;
+        #[cfg(target_os = "linux")]
+        {
+            state.catch_stack_overflow = false;
+        }
+        #[cfg(feature = "use-ctrlc")]
+        {
+            state.treat_sigint_as_fatal = false;
+        }
+        #[cfg(feature = "use-log")]
+        {
+            state.capture_error_logs = false;
+        }
+        state.log_rate_limiter = None;
+        state.soft_panic_threshold = DEFAULT_SOFT_PANIC_THRESHOLD;
+        state.retain_payload = false;
+        state.breadcrumb_fn = None;
+        state.scope_history_len = DEFAULT_SCOPE_HISTORY_LEN;
+        state.backtrace_format = BacktraceFormat::Full;
+        state.verbosity = Verbosity::Full;
+        state.quiet = false;
+        state.stderr_fallback = true;
+        state.resolver_tx = None; // stops the thread
+        state.first_panic_fn = None;
+        state.panic_log_file = None;
+        #[cfg(target_os = "linux")]
+        {
+            state.persistent_ring = None;
+        }
+        state.clock = None;
+        state.backtrace_capturer = None;
+        state.thread_id_formatter = None;
+        state.unknown_payload_message = None;
+        state.suppressions.clear();
+        state.max_message_len = None;
+        state.max_total_backtrace_bytes = None;
+        state.scope_name = None;
+        state.panics_consumed = false;
+        state.capture_backtraces = true;
+        state.capture_cpu_time = false;
+        state.capture_live_thread_count = false;
+        state.capture_first_only = false;
         state.is_running = false;
+        state.scope_owner = None;
+        state.scope_started = None;
+        state.capture_paused = false;
+        state.drain_on_first_panic = false;
 
         #[cfg(feature = "use-slog")]
         {
-            state.slogger = default_slogger();
+            if std::mem::take(&mut state.reset_slogger) {
+                state.slogger = default_slogger();
+            }
+        }
+
+        #[cfg(feature = "use-tokio")]
+        {
+            state.cancellation_token = None;
+        }
+        state.panic_semaphore = None;
+    }
+}
+
+impl DeferredLog {
+    fn log(&self, target: &LogTarget) {
+        match self {
+            DeferredLog::Info(msg) => log_info!(target, "{}", msg),
+            DeferredLog::Warn(msg) => log_warn!(target, "{}", msg),
+            DeferredLog::Error(msg) => log_error!(target, "{}", msg),
+            DeferredLog::Crit(msg) => log_crit!(target, "{}", msg),
+        }
+    }
+}
+
+impl State {
+    /// Updates [HAS_PANICKED], which must be called whenever the stored panics or panic budget
+    /// change.
+    fn update_has_panicked(&self) {
+        let has_panicked = if self.panic_budget.is_some() {
+            self.budget_exceeded
+        } else {
+            !self.panics.is_empty()
+        };
+        HAS_PANICKED.store(has_panicked, std::sync::atomic::Ordering::Release);
+    }
+
+    /// Enters drain mode if enabled by [Builder::drain_on_first_panic] and the scope has failed,
+    /// i.e. its panics won't be tolerated by [Builder::soft_panic_threshold] or
+    /// [Builder::panic_budget].
+    fn drain_if_failed(&self) {
+        let new_panics = self.panics.len().saturating_sub(self.preexisting_panics);
+        let failed = HAS_PANICKED.load(std::sync::atomic::Ordering::Acquire)
+            && new_panics >= self.soft_panic_threshold;
+        if self.drain_on_first_panic && failed {
+            enter_drain_mode();
+        }
+    }
+
+    fn panic_describer(&self, thread: ThreadId) -> PanicDescriber {
+        PanicDescriber {
+            thread_label: self.thread_labels.get(&thread).cloned(),
+            thread_id_formatter: self.thread_id_formatter.clone(),
+            unknown_payload_message: self.unknown_payload_message.clone(),
+        }
+    }
+
+    fn log_target(&self) -> LogTarget {
+        LogTarget {
+            quiet: self.quiet,
+            #[cfg(not(any(feature = "use-slog", feature = "use-log", feature = "use-stderr")))]
+            stderr_fallback: self.stderr_fallback,
+            #[cfg(feature = "use-slog")]
+            slogger: self.slogger.clone(),
+        }
+    }
+
+    /// The current time according to [Builder::clock].
+    fn now(&self) -> Instant {
+        match &self.clock {
+            Some(clock) => clock(),
+            None => Instant::now(),
+        }
+    }
+
+    /// Remembers a registry whose thread exited for [merge_all_registries], forgetting any that
+    /// have since been dropped.
+    fn remember_exited_registry(&mut self, registry: &Registry) {
+        self.exited_registries.retain(|exited| {
+            exited.strong_count() > 0 && !std::ptr::eq(exited.as_ptr(), Arc::as_ptr(&registry.0))
+        });
+        self.exited_registries.push(Arc::downgrade(&registry.0));
+    }
+
+    fn tolerated_panic_logs(&self, from: usize) -> Vec<DeferredLog> {
+        self.panics[from..]
+            .iter()
+            .map(|panic| {
+                DeferredLog::Warn(format!(
+                    "panic on thread {:?}: {:?}",
+                    panic.thread, panic.message
+                ))
+            })
+            .collect()
+    }
+
+    fn record_scope_stat(&mut self, stat: ScopeStat) {
+        self.scope_history.push_back(stat);
+        while self.scope_history.len() > self.scope_history_len {
+            self.scope_history.pop_front();
+        }
+    }
+
+    /// Stores a panic, evicting the oldest backtraces if needed to stay within
+    /// [Builder::max_total_backtrace_bytes].
+    fn push_panic(&mut self, panic: Panic) {
+        let Some(max_bytes) = self.max_total_backtrace_bytes else {
+            self.panics.push(panic);
+            return;
+        };
+
+        if panic.backtrace_captured {
+            self.total_backtrace_bytes += estimated_backtrace_bytes(&panic.backtrace);
+        }
+        self.panics.push(panic);
+
+        while self.total_backtrace_bytes > max_bytes {
+            let Some(panic) = self.panics.get_mut(self.eviction_cursor) else {
+                break;
+            };
+            self.eviction_cursor += 1;
+
+            if panic.backtrace_captured {
+                // may differ from when it was counted if it has since been resolved
+                self.total_backtrace_bytes = self
+                    .total_backtrace_bytes
+                    .saturating_sub(estimated_backtrace_bytes(&panic.backtrace));
+                panic.backtrace = Backtrace::from(Vec::new());
+                panic.backtrace_captured = false;
+                panic.backtrace_resolved = false;
+                self.backtraces_evicted += 1;
+            }
+        }
+    }
+
+    /// Recounts [State::total_backtrace_bytes] from scratch, which must be called whenever
+    /// stored panics are removed.
+    fn recount_backtrace_bytes(&mut self) {
+        self.eviction_cursor = 0;
+        self.total_backtrace_bytes = match self.max_total_backtrace_bytes {
+            Some(_) => self
+                .panics
+                .iter()
+                .filter(|p| p.backtrace_captured)
+                .map(|p| estimated_backtrace_bytes(&p.backtrace))
+                .sum(),
+            None => 0,
+        };
+    }
+
+    /// Attaches the payload to the most recent panic on the current thread.
+    fn retain_payload_of_current_thread(&mut self, payload: Box<dyn Any + Send>) {
+        let tid = std::thread::current().id();
+        if let Some(panic) = self.panics.iter_mut().rev().find(|p| p.thread_id == tid) {
+            panic.payload = Some(Arc::new(std::sync::Mutex::new(payload)));
         }
     }
 }
@@ -480,10 +5808,88 @@ impl Default for State {
         State {
             panics: Vec::new(),
             backtrace_resolution_limit: DEFAULT_BACKTRACE_RESOLUTION_LIMIT,
+            backtrace_resolution_time_budget: None,
+            preserve_panics: false,
+            grace_period: Duration::ZERO,
+            max_scope_duration: None,
+            log_rate_limiter: None,
+            soft_panic_threshold: DEFAULT_SOFT_PANIC_THRESHOLD,
+            retain_payload: false,
+            thread_labels: HashMap::new(),
+            registries: HashMap::new(),
+            exited_registries: Vec::new(),
+            breadcrumb_fn: None,
+            scope_history_len: DEFAULT_SCOPE_HISTORY_LEN,
+            build_info: None,
+            env_snapshot: Vec::new(),
+            backtrace_format: BacktraceFormat::Full,
+            verbosity: Verbosity::Full,
+            quiet: false,
+            stderr_fallback: true,
+            resolver_tx: None,
+            first_panic_fn: None,
+            panic_log_file: None,
+            #[cfg(target_os = "linux")]
+            persistent_ring: None,
+            clock: None,
+            backtrace_capturer: None,
+            thread_id_formatter: None,
+            unknown_payload_message: None,
+            suppressions: Vec::new(),
+            max_message_len: None,
+            max_total_backtrace_bytes: None,
+            total_backtrace_bytes: 0,
+            eviction_cursor: 0,
+            backtraces_evicted: 0,
+            first_panic_captured: false,
+            panics_dropped: 0,
+            scope_name: None,
+            require_handled: false,
+            drain_on_first_panic: false,
+            preexisting_panics: 0,
+            panics_consumed: false,
+            panic_budget: None,
+            capture_backtraces: true,
+            capture_cpu_time: false,
+            capture_live_thread_count: false,
+            capture_first_only: false,
+            capture_after_scope: false,
+            #[cfg(target_os = "linux")]
+            catch_stack_overflow: false,
+            #[cfg(feature = "use-ctrlc")]
+            treat_sigint_as_fatal: false,
+            abort_on_main_thread_panic: None,
+            exit_fn: None,
+            post_scope_panics: Vec::new(),
+            #[cfg(feature = "use-log")]
+            capture_error_logs: false,
+            #[cfg(feature = "use-log")]
+            soft_panics: Vec::new(),
+            budget_timestamps: VecDeque::new(),
+            budget_exceeded: false,
             is_running: false,
+            scope_owner: None,
+            last_scope_owner: None,
+            scope_started: None,
+            capture_paused: false,
+            spawn_sites: HashMap::new(),
+            last_scope: None,
+            last_attempt_count: 0,
+            next_sequence: 1,
+            scope_history: VecDeque::new(),
+            capture_healthy: true,
+            post_scope_hook: None,
 
             #[cfg(feature = "use-slog")]
             slogger: default_slogger(),
+            #[cfg(feature = "use-slog")]
+            reset_slogger: false,
+
+            #[cfg(feature = "use-tokio")]
+            panic_sender: tokio::sync::broadcast::channel(PANIC_STREAM_CAPACITY).0,
+            #[cfg(feature = "use-tokio")]
+            cancellation_token: None,
+            panic_semaphore: None,
         }
     }
 }
@@ -0,0 +1,27 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+
+mod setup;
+
+#[test]
+fn abort_on_main_thread_panic() {
+    let exit_code = Arc::new(AtomicI32::new(0));
+    let builder = {
+        let exit_code = exit_code.clone();
+        setup::panik_builder()
+            .abort_on_main_thread_panic(3)
+            .exit_fn(move |code| exit_code.store(code, Ordering::SeqCst))
+    };
+
+    let result = builder.clone().run_and_handle_panics(|| {
+        std::thread::spawn(|| panic!("worker")).join().unwrap_err();
+    });
+    assert!(result.is_none());
+    assert_eq!(panik::panics().len(), 1);
+    assert_eq!(exit_code.load(Ordering::SeqCst), 0);
+
+    let result = builder.run_and_handle_panics(|| panic!("owner"));
+    assert!(result.is_none());
+    assert_eq!(panik::panics().len(), 1);
+    assert_eq!(exit_code.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,31 @@
+mod setup;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+static RESOLVED_IN_SCOPE: AtomicBool = AtomicBool::new(false);
+
+#[test]
+fn background_resolver() {
+    let result = setup::panik_builder()
+        .background_resolver(true)
+        .run_and_handle_panics(|| {
+            let _ = std::thread::spawn(|| panic!("resolve me")).join();
+
+            let deadline = Instant::now() + Duration::from_secs(30);
+            while Instant::now() < deadline {
+                if panik::panics()[0].is_backtrace_resolved() {
+                    RESOLVED_IN_SCOPE.store(true, Ordering::Relaxed);
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        });
+
+    assert!(result.is_none());
+    assert!(RESOLVED_IN_SCOPE.load(Ordering::Relaxed));
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert!(panics[0].is_backtrace_resolved());
+}
@@ -0,0 +1,18 @@
+mod setup;
+
+use backtrace::Backtrace;
+
+#[test]
+fn backtrace_capturer() {
+    let result = setup::panik_builder()
+        .backtrace_capturer(|| {
+            let frames = Backtrace::new_unresolved().frames()[..3].to_vec();
+            Backtrace::from(frames)
+        })
+        .run_and_handle_panics(|| panic!("shallow"));
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    let backtrace = panics[0].backtrace_opt().unwrap();
+    assert_eq!(backtrace.frames().len(), 3);
+}
@@ -0,0 +1,22 @@
+mod setup;
+
+#[inline(never)]
+fn distinctively_named_panicker() {
+    panic!("oh no");
+}
+
+#[test]
+fn backtrace_contains_symbol() {
+    let result = setup::panik_builder()
+        .run_and_handle_panics(|| std::thread::spawn(distinctively_named_panicker).join());
+    assert!(result.is_none());
+
+    let panic = &panik::panics()[0];
+    assert!(panic.is_backtrace_resolved());
+    assert!(panic.backtrace_contains_symbol("distinctively_named_panicker"));
+    assert!(!panic.backtrace_contains_symbol("not_a_function_in_this_backtrace"));
+
+    // unresolved backtraces never match
+    let unresolved = panic.clone_without_backtrace();
+    assert!(!unresolved.backtrace_contains_symbol("distinctively_named_panicker"));
+}
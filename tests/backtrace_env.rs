@@ -0,0 +1,48 @@
+use panik::BacktraceFormat;
+
+fn is_full() -> bool {
+    panik::panics()[0]
+        .backtrace_string()
+        .contains("std::panicking")
+}
+
+#[test]
+fn backtrace_env() {
+    // honoured by default
+    std::env::set_var("RUST_BACKTRACE", "0");
+    let builder = panik::Builder::new();
+
+    let _ = builder
+        .clone()
+        .run_and_handle_panics(|| panic!("uncaptured"));
+    assert!(panik::panics()[0].backtrace().frames().is_empty());
+
+    let _ = builder
+        .clone()
+        .capture_backtraces(true)
+        .run_and_handle_panics(|| panic!("forced"));
+    assert!(!panik::panics()[0].backtrace().frames().is_empty());
+
+    // the value picks the format, unless set on the builder
+    std::env::set_var("RUST_BACKTRACE", "1");
+    let _ = builder.clone().run_and_handle_panics(|| panic!("short"));
+    assert!(!is_full());
+
+    std::env::set_var("RUST_BACKTRACE", "full");
+    let _ = builder.clone().run_and_handle_panics(|| panic!("full"));
+    assert!(is_full());
+
+    std::env::set_var("RUST_BACKTRACE", "1");
+    let _ = builder
+        .clone()
+        .backtrace_format(BacktraceFormat::Full)
+        .run_and_handle_panics(|| panic!("explicit"));
+    assert!(is_full());
+
+    // ignored if disabled
+    std::env::remove_var("RUST_BACKTRACE");
+    let _ = builder
+        .backtrace_env_control(false)
+        .run_and_handle_panics(|| panic!("always"));
+    assert!(!panik::panics()[0].backtrace().frames().is_empty());
+}
@@ -0,0 +1,24 @@
+mod setup;
+
+use panik::BacktraceFormat;
+
+#[test]
+fn backtrace_format() {
+    let builder = setup::panik_builder();
+
+    let _ = builder
+        .clone()
+        .backtrace_format(BacktraceFormat::Full)
+        .run_and_handle_panics(|| panic!("full"));
+    let full = panik::panics()[0].backtrace_string();
+
+    let _ = builder
+        .backtrace_format(BacktraceFormat::Short)
+        .run_and_handle_panics(|| panic!("short"));
+    let short = panik::panics()[0].backtrace_string();
+
+    assert!(full.contains("std::"));
+    assert!(short.contains("backtrace_format"));
+    assert!(short.len() < full.len());
+    assert!(!short.contains("std::panicking"));
+}
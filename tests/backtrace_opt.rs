@@ -0,0 +1,17 @@
+mod setup;
+
+#[test]
+fn backtrace_opt() {
+    let builder = setup::panik_builder();
+
+    let _ = builder.clone().run_and_handle_panics(|| panic!("captured"));
+    assert!(panik::panics()[0].backtrace_opt().is_some());
+
+    let _ = builder
+        .capture_backtraces(false)
+        .run_and_handle_panics(|| panic!("not captured"));
+    assert!(panik::panics()[0].backtrace_opt().is_none());
+
+    let _ = panik::run_and_handle_panics(|| panik::record_synthetic_panic("synthetic"));
+    assert!(panik::panics()[0].backtrace_opt().is_none());
+}
@@ -0,0 +1,20 @@
+mod setup;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static FRAME: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn breadcrumbs() {
+    let result = setup::panik_builder()
+        .breadcrumb_fn(|| vec![format!("frame {}", FRAME.load(Ordering::Relaxed))])
+        .run_and_handle_panics(|| {
+            FRAME.store(42, Ordering::Relaxed);
+            panic!("oh no")
+        });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(panics[0].breadcrumbs(), ["frame 42"]);
+}
@@ -0,0 +1,18 @@
+mod setup;
+
+use panik::BuildInfo;
+
+#[test]
+fn build_info() {
+    let result = setup::panik_builder()
+        .build_info(BuildInfo {
+            version: "1.2.3".to_owned(),
+            git_sha: "abcdef0".to_owned(),
+            build_timestamp: "2021-01-01T00:00:00Z".to_owned(),
+        })
+        .run_and_handle_panics(|| panic!("oh no"));
+    assert!(result.is_none());
+
+    let report = panik::render_report();
+    assert!(report.contains("build: 1.2.3 (abcdef0, built 2021-01-01T00:00:00Z)\n"));
+}
@@ -0,0 +1,25 @@
+use panik::{BacktraceFormat, Builder, NestedPolicy};
+use std::time::Duration;
+
+#[test]
+fn builder_getters() {
+    let default = Builder::new();
+    assert_eq!(default.get_backtrace_resolution_limit(), 8);
+    assert_eq!(default.get_grace_period(), Duration::ZERO);
+    assert_eq!(default.get_log_rate_limit(), None);
+    assert_eq!(default.get_retry(), 1);
+    assert_eq!(default.get_on_nested(), NestedPolicy::Panic);
+    assert!(default.get_build_info().is_none());
+
+    let builder = Builder::new()
+        .backtrace_resolution_limit(2)
+        .grace_period(Duration::from_secs(1))
+        .log_rate_limit(10)
+        .backtrace_format(BacktraceFormat::Short)
+        .quiet(true);
+    assert_eq!(builder.get_backtrace_resolution_limit(), 2);
+    assert_eq!(builder.get_grace_period(), Duration::from_secs(1));
+    assert_eq!(builder.get_log_rate_limit(), Some(10));
+    assert_eq!(builder.get_backtrace_format(), Some(BacktraceFormat::Short));
+    assert!(builder.get_quiet());
+}
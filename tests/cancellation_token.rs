@@ -0,0 +1,24 @@
+#![cfg(feature = "use-tokio")]
+
+mod setup;
+
+use tokio_util::sync::CancellationToken;
+
+#[tokio::test]
+async fn cancellation_token() {
+    let token = CancellationToken::new();
+    let task = tokio::spawn({
+        let token = token.clone();
+        async move { token.cancelled().await }
+    });
+
+    let result = setup::panik_builder()
+        .cancellation_token(token.clone())
+        .run_and_handle_panics(|| {
+            let _ = std::thread::spawn(|| panic!("cancel everything")).join();
+        });
+    assert!(result.is_none());
+
+    assert!(token.is_cancelled());
+    task.await.unwrap();
+}
@@ -0,0 +1,53 @@
+mod setup;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+static CUSTOM_HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn capture_after_scope() {
+    let builder = setup::panik_builder().capture_after_scope(true);
+    let (tx, rx) = mpsc::channel::<()>();
+    let result = builder.clone().run_and_handle_panics(move || {
+        std::thread::spawn(move || {
+            let _ = rx.recv();
+            panic!("late worker")
+        })
+    });
+    let late_worker = result.expect("no panic in scope");
+
+    // panic after the scope has ended
+    drop(tx);
+    assert!(late_worker.join().is_err());
+    assert!(panik::panics().is_empty());
+    assert!(!panik::has_panicked());
+
+    let post_scope = panik::post_scope_panics();
+    assert_eq!(post_scope.len(), 1);
+    assert_eq!(post_scope[0].message(), "late worker");
+
+    // cleared by the next scope
+    let result = panik::run_and_handle_panics(|| 5);
+    assert_eq!(result, Some(5));
+    assert!(panik::post_scope_panics().is_empty());
+
+    // the hook from before the scope is restored rather than chained onto
+    std::panic::set_hook(Box::new(|_| {
+        CUSTOM_HOOK_CALLS.fetch_add(1, Ordering::SeqCst);
+    }));
+    for i in 1..=3 {
+        let result = builder.clone().run_and_handle_panics(|| 5);
+        assert_eq!(result, Some(5));
+
+        assert!(std::thread::spawn(|| panic!("late")).join().is_err());
+        assert_eq!(panik::post_scope_panics().len(), 1);
+        assert_eq!(CUSTOM_HOOK_CALLS.load(Ordering::SeqCst), i);
+    }
+
+    let result = panik::run_and_handle_panics(|| 5);
+    assert_eq!(result, Some(5));
+    assert!(std::thread::spawn(|| panic!("late")).join().is_err());
+    assert!(panik::post_scope_panics().is_empty());
+    assert_eq!(CUSTOM_HOOK_CALLS.load(Ordering::SeqCst), 4);
+}
@@ -0,0 +1,43 @@
+mod setup;
+
+#[test]
+fn capture_env_vars() {
+    std::env::set_var("PANIK_TEST_FLAG", "enabled");
+    std::env::remove_var("PANIK_TEST_UNSET");
+
+    let path = std::env::temp_dir().join(format!("panik-env-{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let result = setup::panik_builder()
+        .panic_log_file(&path)
+        .capture_env_vars(vec![
+            "PANIK_TEST_FLAG".to_owned(),
+            "PANIK_TEST_UNSET".to_owned(),
+        ])
+        .run_and_handle_panics(|| {
+            std::env::set_var("PANIK_TEST_FLAG", "changed");
+            panic!("oh no")
+        });
+    assert!(result.is_none());
+
+    let report = panik::render_report();
+    assert!(report.contains("env: PANIK_TEST_FLAG=enabled\n"));
+    assert!(report.contains("env: PANIK_TEST_UNSET (unset)\n"));
+    assert!(!report.contains("changed"));
+
+    let read = panik::read_panics_from_file(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(read.len(), 1);
+    assert_eq!(
+        read[0].env_vars(),
+        [
+            ("PANIK_TEST_FLAG".to_owned(), Some("enabled".to_owned())),
+            ("PANIK_TEST_UNSET".to_owned(), None),
+        ]
+    );
+
+    // not carried over to the next scope
+    let result = panik::run_and_handle_panics(|| panic!("oh no"));
+    assert!(result.is_none());
+    assert!(!panik::render_report().contains("env: "));
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "use-log")]
+
+use std::sync::Mutex;
+
+static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CapturingLogger;
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        MESSAGES.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn capture_error_logs() {
+    log::set_boxed_logger(Box::new(panik::ErrorLogCapture::new(CapturingLogger))).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let result = panik::Builder::new()
+        .capture_error_logs(true)
+        .run_and_handle_panics(|| {
+            log::warn!("just a warning");
+            log::error!("disk full");
+            panik::has_panicked()
+        });
+    assert_eq!(result, Some(false));
+
+    let soft_panics = panik::soft_panics();
+    assert_eq!(soft_panics.len(), 1);
+    assert_eq!(soft_panics[0].message(), "disk full");
+    assert!(panik::panics().is_empty());
+
+    // still passed through
+    assert!(MESSAGES.lock().unwrap().contains(&"disk full".to_owned()));
+
+    // panik's own error logs aren't captured
+    let result = panik::Builder::new()
+        .capture_error_logs(true)
+        .run_and_handle_panics(|| std::panic::panic_any("real"));
+    assert!(result.is_none());
+    assert!(panik::soft_panics().is_empty());
+}
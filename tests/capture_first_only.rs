@@ -0,0 +1,36 @@
+mod setup;
+
+use panik::Registry;
+
+#[test]
+fn capture_first_only() {
+    let registry = Registry::new("worker");
+    let result = setup::panik_builder()
+        .capture_first_only(true)
+        .run_and_handle_panics(|| {
+            for msg in &["root cause", "fallout", "more fallout"] {
+                let _ = std::thread::spawn(move || std::panic::panic_any(*msg)).join();
+            }
+
+            // registries aren't affected by the global set's first panic
+            let registry = registry.clone();
+            let _ = std::thread::spawn(move || {
+                registry.register_current_thread();
+                panic!("registered")
+            })
+            .join();
+
+            panic!("even more fallout");
+        });
+    assert!(result.is_none());
+
+    let registry_panics = registry.panics();
+    assert_eq!(registry_panics.len(), 1);
+    assert_eq!(registry_panics[0].message(), "registered");
+
+    assert!(panik::has_panicked());
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(panics[0].message(), "root cause");
+    assert_eq!(panik::panics_dropped(), 3);
+}
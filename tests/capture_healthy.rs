@@ -0,0 +1,30 @@
+mod setup;
+
+#[test]
+fn capture_healthy() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        assert!(panik::is_capture_healthy());
+        panic!("oh no")
+    });
+
+    assert!(result.is_none());
+    assert!(panik::is_capture_healthy());
+
+    // the sentinel panic is never recorded
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(panics[0].message(), "oh no");
+
+    // detected once the scope ends if another library replaces the hook
+    let builder = panik::Builder::new();
+    let result = builder.clone().run_and_handle_panics(|| {
+        std::panic::set_hook(Box::new(|_| {}));
+        "replaced"
+    });
+    assert_eq!(result, Some("replaced"));
+    assert!(!panik::is_capture_healthy());
+
+    let result = builder.run_and_handle_panics(|| "restored");
+    assert_eq!(result, Some("restored"));
+    assert!(panik::is_capture_healthy());
+}
@@ -0,0 +1,52 @@
+#![cfg(target_os = "linux")]
+
+mod setup;
+
+use std::process::Command;
+
+const CHILD_ENV: &str = "PANIK_STACK_OVERFLOW_LOG";
+
+fn recurse(depth: u64) -> u64 {
+    if depth == u64::MAX {
+        return 0;
+    }
+
+    let frame = [depth; 64];
+    std::hint::black_box(&frame);
+    recurse(depth + 1) + frame[0]
+}
+
+#[test]
+fn catch_stack_overflow() {
+    // the overflow aborts the process, so it happens in a child process running this same test
+    if let Some(path) = std::env::var_os(CHILD_ENV) {
+        let _ = setup::panik_builder()
+            .catch_stack_overflow(true)
+            .panic_log_file(path)
+            .run_and_handle_panics(|| {
+                let _ = std::thread::Builder::new()
+                    .name("recursive".to_owned())
+                    .spawn(|| recurse(0))
+                    .unwrap()
+                    .join();
+            });
+        unreachable!("stack overflow should abort");
+    }
+
+    let path = std::env::temp_dir().join(format!("panik-overflow-{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let status = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "catch_stack_overflow", "--nocapture"])
+        .env(CHILD_ENV, &path)
+        .status()
+        .unwrap();
+    assert!(!status.success());
+
+    let read = panik::read_panics_from_file(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(read.len(), 1);
+    assert_eq!(read[0].message(), "stack overflow");
+    assert!(read[0].thread_name().ends_with("(recursive)"));
+}
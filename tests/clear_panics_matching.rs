@@ -0,0 +1,22 @@
+mod setup;
+
+#[test]
+fn clear_panics_matching() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        for msg in &["recoverable", "fatal", "recoverable"] {
+            let _ = std::thread::spawn(move || std::panic::panic_any(*msg)).join();
+        }
+    });
+    assert!(result.is_none());
+    assert_eq!(panik::panics().len(), 3);
+
+    panik::clear_panics_matching(|panic| panic.message() == "recoverable");
+    let remaining = panik::panics();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].message(), "fatal");
+    assert!(panik::has_panicked());
+
+    panik::clear_panics_matching(|_| true);
+    assert!(panik::panics().is_empty());
+    assert!(!panik::has_panicked());
+}
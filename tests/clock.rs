@@ -0,0 +1,39 @@
+mod setup;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn clock() {
+    let base = Instant::now();
+    let fake_secs = Arc::new(AtomicU64::new(0));
+
+    let builder = setup::panik_builder()
+        .panic_budget(1, Duration::from_secs(10))
+        .clock({
+            let fake_secs = fake_secs.clone();
+            move || base + Duration::from_secs(fake_secs.load(Ordering::Relaxed))
+        });
+
+    // spread out in fake time, so never more than 1 in the window
+    let result = builder.clone().run_and_handle_panics(|| {
+        for _ in 0..3 {
+            let _ = std::thread::spawn(|| panic!("spread out")).join();
+            fake_secs.fetch_add(20, Ordering::Relaxed);
+        }
+        "fine"
+    });
+    assert_eq!(result, Some("fine"));
+    assert!(!panik::budget_exceeded());
+
+    // all at the same fake instant
+    let result = builder.run_and_handle_panics(|| {
+        for _ in 0..2 {
+            let _ = std::thread::spawn(|| panic!("burst")).join();
+        }
+        "fine"
+    });
+    assert!(result.is_none());
+    assert!(panik::budget_exceeded());
+}
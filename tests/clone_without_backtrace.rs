@@ -0,0 +1,19 @@
+mod setup;
+
+#[test]
+fn clone_without_backtrace() {
+    let result = setup::panik_builder().run_and_handle_panics(|| panic!("oh no"));
+    assert!(result.is_none());
+
+    let panic = &panik::panics()[0];
+    assert!(panic.is_backtrace_resolved());
+
+    let clone = panic.clone_without_backtrace();
+    assert_eq!(&clone, panic);
+    assert_eq!(clone.message(), panic.message());
+    assert_eq!(clone.thread_id(), panic.thread_id());
+    assert_eq!(clone.location(), panic.location());
+    assert!(!clone.is_backtrace_resolved());
+    assert!(clone.backtrace().frames().is_empty());
+    assert!(clone.backtrace_opt().is_none());
+}
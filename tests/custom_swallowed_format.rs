@@ -0,0 +1,29 @@
+mod setup;
+
+use std::borrow::Cow;
+use std::cell::Cell;
+
+#[test]
+fn custom_swallowed_format() {
+    let builder = setup::panik_builder();
+    let formatted = Cell::new(None);
+
+    let result = builder.clone().run_and_handle_panics_with(
+        || "secret password",
+        |_| -> Cow<'static, str> { panic!("not swallowed so shouldn't be formatted") },
+    );
+    assert_eq!(result, Some("secret password"));
+
+    let result = builder.run_and_handle_panics_with(
+        || {
+            let _ = std::thread::spawn(|| panic!("oh no")).join();
+            "secret password"
+        },
+        |res| {
+            formatted.set(Some(res.len()));
+            Cow::Borrowed("<redacted>")
+        },
+    );
+    assert!(result.is_none());
+    assert_eq!(formatted.get(), Some("secret password".len()));
+}
@@ -0,0 +1,34 @@
+mod setup;
+
+#[test]
+fn deduplicated_panics() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let workers: Vec<_> = (0..4)
+            .map(|_| std::thread::spawn(|| panic!("pool exhausted")))
+            .collect();
+        for worker in workers {
+            let _ = worker.join();
+        }
+
+        let _ = std::thread::spawn(|| panic!("something else")).join();
+    });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 5);
+    assert_ne!(panics[0].thread_id(), panics[1].thread_id());
+    assert_eq!(panics[0].key(), panics[1].key());
+
+    let deduplicated = panik::deduplicated_panics();
+    assert_eq!(deduplicated.len(), 2);
+
+    assert_eq!(deduplicated[0].count(), 4);
+    let key = deduplicated[0].panic().key();
+    assert_eq!(key.message(), "pool exhausted");
+    let location = key.location().unwrap();
+    assert!(location.file().ends_with("deduplicated_panics.rs"));
+    assert_eq!(location.line(), 7);
+
+    assert_eq!(deduplicated[1].count(), 1);
+    assert_eq!(deduplicated[1].panic().message(), "something else");
+}
@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod setup;
+
+#[test]
+fn drain_mode() {
+    let builder = setup::panik_builder().drain_on_first_panic(true);
+
+    let result = builder.clone().run_and_handle_panics(|| {
+        assert!(!panik::is_draining());
+        let worker = std::thread::spawn(|| {
+            let mut tasks = 0;
+            while !panik::is_draining() {
+                tasks += 1;
+                if tasks == 3 {
+                    panic!("task failed");
+                }
+            }
+            tasks
+        });
+        let _ = worker.join();
+        panik::is_draining()
+    });
+    assert!(result.is_none());
+    assert!(panik::is_draining());
+
+    // reset at the start of the next scope, and can be disabled
+    let drained_on_panic = Arc::new(AtomicBool::new(true));
+    let result = builder
+        .clone()
+        .drain_on_first_panic(false)
+        .run_and_handle_panics({
+            let drained_on_panic = drained_on_panic.clone();
+            move || {
+                assert!(!panik::is_draining());
+                let _ = std::thread::spawn(|| panic!("oh no")).join();
+                drained_on_panic.store(panik::is_draining(), Ordering::SeqCst);
+
+                panik::enter_drain_mode();
+            }
+        });
+    assert!(result.is_none());
+    assert!(!drained_on_panic.load(Ordering::SeqCst));
+    assert!(panik::is_draining());
+
+    // tolerated panics don't drain, only once the scope has failed
+    let drained_when_tolerated = Arc::new(AtomicBool::new(true));
+    let result = builder
+        .clone()
+        .soft_panic_threshold(2)
+        .run_and_handle_panics({
+            let drained_when_tolerated = drained_when_tolerated.clone();
+            move || {
+                let _ = std::thread::spawn(|| panic!("tolerated")).join();
+                drained_when_tolerated.store(panik::is_draining(), Ordering::SeqCst);
+                let _ = std::thread::spawn(|| panic!("fatal")).join();
+            }
+        });
+    assert!(result.is_none());
+    assert!(!drained_when_tolerated.load(Ordering::SeqCst));
+    assert!(panik::is_draining());
+
+    let result = builder
+        .clone()
+        .panic_budget(1, Duration::from_secs(60))
+        .run_and_handle_panics(|| {
+            let _ = std::thread::spawn(|| panic!("within budget")).join();
+            panik::is_draining()
+        });
+    assert_eq!(result, Some(false));
+
+    // disabled by default
+    let result = panik::run_and_handle_panics(|| {
+        assert!(!panik::is_draining());
+        let _ = std::thread::spawn(|| panic!("oh no")).join();
+        panik::is_draining()
+    });
+    assert!(result.is_none());
+    assert!(!panik::is_draining());
+}
@@ -0,0 +1,20 @@
+mod setup;
+
+#[test]
+fn epoch() {
+    let builder = setup::panik_builder();
+
+    let _ = builder.clone().run_and_handle_panics(|| {
+        for msg in &["first", "second", "third"] {
+            let _ = std::thread::spawn(move || std::panic::panic_any(*msg)).join();
+        }
+    });
+    let first_scope: Vec<_> = panik::panics().iter().map(|p| p.epoch()).collect();
+    assert_eq!(first_scope.len(), 3);
+    assert!(first_scope[0] > 0);
+    assert!(first_scope.windows(2).all(|w| w[0] < w[1]));
+
+    // never reset between scopes
+    let _ = builder.run_and_handle_panics(|| panic!("later"));
+    assert!(panik::panics()[0].epoch() > first_scope[2]);
+}
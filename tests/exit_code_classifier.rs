@@ -0,0 +1,57 @@
+mod setup;
+
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+static EXITED_WITH: AtomicI32 = AtomicI32::new(0);
+
+struct Exited;
+
+#[test]
+fn exit_code_classifier() {
+    let builder = setup::panik_builder()
+        .failure_action(|panics| assert_eq!(panics.len(), 2))
+        .exit_code_classifier(|panics| {
+            if panics.iter().any(|p| p.message().contains("config")) {
+                78
+            } else {
+                70
+            }
+        })
+        .exit_fn(|code| {
+            EXITED_WITH.store(code, Ordering::Relaxed);
+
+            // bail out before the process exits
+            std::panic::resume_unwind(Box::new(Exited));
+        });
+
+    let exited = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        builder.clone().run_and_handle_panics_strict(|| {
+            let _ = std::thread::spawn(|| panic!("bad config")).join();
+            panic!("oh no")
+        })
+    }));
+    assert!(exited.unwrap_err().is::<Exited>());
+    assert_eq!(EXITED_WITH.load(Ordering::Relaxed), 78);
+
+    let exited = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        builder.run_and_handle_panics_strict(|| {
+            let _ = std::thread::spawn(|| panic!("oh no")).join();
+            panic!("oh no again")
+        })
+    }));
+    assert!(exited.unwrap_err().is::<Exited>());
+    assert_eq!(EXITED_WITH.load(Ordering::Relaxed), 70);
+
+    // exits with 1 by default
+    let exited = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        panik::Builder::new()
+            .exit_fn(|code| {
+                EXITED_WITH.store(code, Ordering::Relaxed);
+                std::panic::resume_unwind(Box::new(Exited));
+            })
+            .run_and_handle_panics_strict(|| -> i32 { panic!("oh no") })
+    }));
+    assert!(exited.unwrap_err().is::<Exited>());
+    assert_eq!(EXITED_WITH.load(Ordering::Relaxed), 1);
+}
@@ -0,0 +1,12 @@
+#[test]
+fn free_fn_backtrace_env() {
+    // the free functions honour the environment like Builder::new
+    std::env::set_var("RUST_BACKTRACE", "0");
+    std::env::remove_var("RUST_LIB_BACKTRACE");
+    let _ = panik::run_and_handle_panics(|| panic!("uncaptured"));
+    assert!(panik::panics()[0].backtrace_opt().is_none());
+
+    std::env::set_var("RUST_BACKTRACE", "1");
+    let _ = panik::run_and_handle_panics(|| panic!("captured"));
+    assert!(panik::panics()[0].backtrace_opt().is_some());
+}
@@ -0,0 +1,20 @@
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn from_panic_info() {
+    let captured = Arc::new(Mutex::new(None));
+    let captured_2 = captured.clone();
+
+    std::panic::set_hook(Box::new(move |info| {
+        *captured_2.lock().unwrap() = Some(panik::Panic::from_panic_info(info));
+    }));
+
+    let result = std::panic::catch_unwind(|| panic!("custom hook"));
+    let _ = std::panic::take_hook();
+    assert!(result.is_err());
+
+    let panic = captured.lock().unwrap().take().expect("hook not called");
+    assert_eq!(panic.message(), "custom hook");
+    assert_eq!(panic.thread_id(), std::thread::current().id());
+    assert!(!panic.is_backtrace_resolved());
+}
@@ -0,0 +1,25 @@
+mod setup;
+
+use std::time::Duration;
+
+#[test]
+fn grace_period() {
+    let result = setup::panik_builder()
+        .grace_period(Duration::from_secs(5))
+        .run_and_handle_panics(|| {
+            // panics shortly after the closure returns, and is never joined
+            std::thread::spawn(|| {
+                std::thread::sleep(Duration::from_millis(100));
+                panic!("too late?")
+            });
+
+            5
+        });
+
+    assert!(result.is_none());
+    assert!(panik::has_panicked());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(panics[0].message(), "too late?");
+}
@@ -0,0 +1,39 @@
+mod setup;
+
+use std::time::Instant;
+
+fn worker_panic() {
+    let _ = std::thread::spawn(|| panic!("oh no")).join();
+}
+
+#[test]
+fn has_panicked_lock_free() {
+    let result = setup::panik_builder()
+        // the clock is called with the lock held, so would deadlock if has_panicked locked
+        .clock(|| {
+            let _ = panik::has_panicked();
+            Instant::now()
+        })
+        .run_and_handle_panics(|| {
+            assert!(!panik::has_panicked());
+
+            worker_panic();
+            assert!(panik::has_panicked());
+            assert_eq!(panik::take_panics().len(), 1);
+            assert!(!panik::has_panicked());
+
+            worker_panic();
+            assert!(panik::has_panicked());
+            panik::clear_panics_matching(|_| true);
+            assert!(!panik::has_panicked());
+
+            worker_panic();
+            assert!(panik::has_panicked());
+            assert_eq!(panik::partition_panics(|_| true).len(), 1);
+            assert!(!panik::has_panicked());
+
+            worker_panic();
+        });
+    assert!(result.is_none());
+    assert!(panik::has_panicked());
+}
@@ -0,0 +1,19 @@
+#![cfg(feature = "test-util")]
+
+mod setup;
+
+#[test]
+fn inject_panic() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        assert!(!panik::has_panicked());
+        panik::inject_panic("injected", "fake-worker");
+        assert!(panik::has_panicked());
+    });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(panics[0].message(), "injected");
+    assert!(panics[0].thread_name().ends_with("(fake-worker)"));
+    assert!(panics[0].backtrace_opt().is_none());
+}
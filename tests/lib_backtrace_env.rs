@@ -0,0 +1,20 @@
+#[test]
+fn lib_backtrace_env() {
+    let builder = panik::Builder::new();
+
+    std::env::set_var("RUST_BACKTRACE", "1");
+    std::env::set_var("RUST_LIB_BACKTRACE", "0");
+    let _ = builder
+        .clone()
+        .run_and_handle_panics(|| panic!("uncaptured"));
+    assert!(panik::panics()[0].backtrace_opt().is_none());
+
+    std::env::set_var("RUST_BACKTRACE", "0");
+    std::env::set_var("RUST_LIB_BACKTRACE", "1");
+    let _ = builder.clone().run_and_handle_panics(|| panic!("captured"));
+    assert!(panik::panics()[0].backtrace_opt().is_some());
+
+    std::env::remove_var("RUST_LIB_BACKTRACE");
+    let _ = builder.run_and_handle_panics(|| panic!("fallback"));
+    assert!(panik::panics()[0].backtrace_opt().is_none());
+}
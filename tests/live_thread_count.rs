@@ -0,0 +1,46 @@
+mod setup;
+
+use std::sync::{Arc, Barrier};
+
+#[test]
+fn live_thread_count() {
+    const IDLE_THREADS: usize = 4;
+
+    let builder = setup::panik_builder();
+
+    let result = builder
+        .clone()
+        .run_and_handle_panics(|| panic!("not captured"));
+    assert!(result.is_none());
+    assert_eq!(panik::panics()[0].live_thread_count(), None);
+
+    let result = builder
+        .capture_live_thread_count(true)
+        .run_and_handle_panics(|| {
+            let barrier = Arc::new(Barrier::new(IDLE_THREADS + 1));
+            let idle = (0..IDLE_THREADS)
+                .map(|_| {
+                    let barrier = barrier.clone();
+                    std::thread::spawn(move || {
+                        barrier.wait();
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let _ = std::thread::spawn(|| panic!("oh no")).join();
+
+            barrier.wait();
+            for thread in idle {
+                thread.join().unwrap();
+            }
+        });
+    assert!(result.is_none());
+
+    let count = panik::panics()[0].live_thread_count();
+    if cfg!(any(target_os = "linux", windows)) {
+        // the idle threads, the panicking thread and the test thread
+        assert!(count.unwrap() >= IDLE_THREADS + 2);
+    } else {
+        assert_eq!(count, None);
+    }
+}
@@ -0,0 +1,17 @@
+mod setup;
+
+#[test]
+fn log_all_panics() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let _ = std::thread::spawn(|| panic!("first")).join();
+        panic!("second")
+    });
+    assert!(result.is_none());
+
+    let before = panik::panics();
+    panik::log_all_panics();
+    panik::log_all_panics();
+
+    assert_eq!(panik::panics(), before);
+    assert_eq!(before.len(), 2);
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "use-log")]
+
+use std::sync::Mutex;
+
+static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CapturingLogger;
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        MESSAGES.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+/// Without slog's default logger, which would forward every line to log a second time.
+fn builder() -> panik::Builder {
+    let builder = panik::Builder::new();
+    #[cfg(feature = "use-slog")]
+    let builder = builder.slogger(slog::Logger::root(slog::Discard, slog::o!()));
+    builder
+}
+
+#[test]
+fn log_clean_exit() {
+    log::set_logger(&CapturingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let clean_exits = || {
+        MESSAGES
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|msg| *msg == "scope completed with no panics")
+            .count()
+    };
+
+    assert_eq!(builder().run_and_handle_panics(|| 1), Some(1));
+    assert_eq!(clean_exits(), 0);
+
+    let result = builder().log_clean_exit(true).run_and_handle_panics(|| 2);
+    assert_eq!(result, Some(2));
+    assert_eq!(clean_exits(), 1);
+}
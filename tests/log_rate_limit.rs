@@ -0,0 +1,54 @@
+#![cfg(feature = "use-log")]
+
+use std::sync::Mutex;
+
+static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CapturingLogger;
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        MESSAGES.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+/// Without slog's default logger, which would forward every line to log a second time.
+fn builder() -> panik::Builder {
+    let builder = panik::Builder::new();
+    #[cfg(feature = "use-slog")]
+    let builder = builder.slogger(slog::Logger::root(slog::Discard, slog::o!()));
+    builder
+}
+
+#[test]
+fn log_rate_limit() {
+    log::set_logger(&CapturingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let result = builder().log_rate_limit(2).run_and_handle_panics(|| {
+        for _ in 0..10 {
+            let _ = std::thread::spawn(|| panic!("storm")).join();
+        }
+    });
+
+    assert!(result.is_none());
+
+    let messages = MESSAGES.lock().unwrap().clone();
+    let logged = messages
+        .iter()
+        .filter(|msg| msg.starts_with("handling panic on thread"))
+        .count();
+    assert_eq!(logged, 2);
+    assert!(messages.contains(&"suppressed logging of 8 panics".to_owned()));
+
+    // only logging is limited, not storage
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 10);
+    assert!(panics.iter().all(|p| p.message() == "storm"));
+}
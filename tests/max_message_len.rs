@@ -0,0 +1,19 @@
+mod setup;
+
+#[test]
+fn max_message_len() {
+    let result = setup::panik_builder()
+        .max_message_len(8)
+        .run_and_handle_panics(|| {
+            let _ = std::thread::spawn(|| std::panic::panic_any("short")).join();
+            let _ = std::thread::spawn(|| std::panic::panic_any("ééééééé long")).join();
+        });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics[0].message(), "short");
+    assert!(!panics[0].message_truncated());
+
+    assert_eq!(panics[1].message(), "éééé…(truncated)");
+    assert!(panics[1].message_truncated());
+}
@@ -0,0 +1,26 @@
+mod setup;
+
+use std::time::Duration;
+
+#[test]
+fn max_scope_duration() {
+    let builder = setup::panik_builder().max_scope_duration(Duration::from_millis(50));
+
+    let result = builder.clone().run_and_handle_panics(|| {
+        while !panik::has_panicked() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        "noticed"
+    });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(panics[0].message(), "scope exceeded max duration of 50ms");
+
+    // finishing in time cancels the watchdog
+    let result = builder.run_and_handle_panics(|| 5);
+    assert_eq!(result, Some(5));
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(!panik::has_panicked());
+}
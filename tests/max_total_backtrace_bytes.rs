@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+mod setup;
+
+#[test]
+fn max_total_backtrace_bytes() {
+    let builder = setup::panik_builder();
+    let result = builder
+        .clone()
+        .max_total_backtrace_bytes(1)
+        .run_and_handle_panics(|| {
+            for _ in 0..3 {
+                let _ = std::thread::spawn(|| panic!("evicted")).join();
+            }
+        });
+    assert!(result.is_none());
+
+    // every backtrace is bigger than a byte
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 3);
+    assert_eq!(panik::backtraces_evicted(), 3);
+    for panic in &panics {
+        assert_eq!(panic.message(), "evicted");
+        assert!(panic.backtrace_opt().is_none());
+    }
+
+    // the background resolver doesn't bring evicted backtraces back
+    let result = builder
+        .clone()
+        .max_total_backtrace_bytes(1)
+        .background_resolver(true)
+        .run_and_handle_panics(|| {
+            let _ = std::thread::spawn(|| panic!("evicted")).join();
+            std::thread::sleep(Duration::from_millis(500));
+        });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert!(panics[0].backtrace_opt().is_none());
+    assert!(!panics[0].is_backtrace_resolved());
+
+    let result = builder.run_and_handle_panics(|| panic!("kept"));
+    assert!(result.is_none());
+    assert_eq!(panik::backtraces_evicted(), 0);
+    assert!(panik::panics()[0].backtrace_opt().is_some());
+}
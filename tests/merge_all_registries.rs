@@ -0,0 +1,43 @@
+mod setup;
+
+#[test]
+fn merge_all_registries() {
+    let audio = panik::Registry::new("audio");
+    let network = panik::Registry::new("network");
+
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let threads = [
+            (Some(audio.clone()), "audio"),
+            (None, "global"),
+            (Some(network.clone()), "network"),
+            (Some(audio.clone()), "audio again"),
+        ];
+        for (registry, msg) in threads.iter().cloned() {
+            std::thread::spawn(move || {
+                if let Some(registry) = registry {
+                    registry.register_current_thread();
+                }
+                std::panic::panic_any(msg);
+            })
+            .join()
+            .unwrap_err();
+        }
+    });
+    assert!(result.is_none());
+
+    let merged = panik::merge_all_registries();
+    let messages: Vec<_> = merged.iter().map(|p| p.message()).collect();
+    assert_eq!(messages, ["audio", "global", "network", "audio again"]);
+    assert!(merged.windows(2).all(|w| w[0].sequence() < w[1].sequence()));
+
+    // sources are left untouched
+    assert_eq!(audio.panics().len(), 2);
+    assert_eq!(network.panics().len(), 1);
+    assert_eq!(panik::panics().len(), 1);
+
+    // exited threads don't keep their registry alive
+    drop(network);
+    let merged = panik::merge_all_registries();
+    let messages: Vec<_> = merged.iter().map(|p| p.message()).collect();
+    assert_eq!(messages, ["audio", "global", "audio again"]);
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "use-metrics")]
+
+use metrics::{Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+mod setup;
+
+/// Shares one value between all metrics with the same name, regardless of labels.
+#[derive(Default)]
+struct CapturingRecorder(Mutex<HashMap<String, Arc<AtomicU64>>>);
+
+impl CapturingRecorder {
+    fn value(&self, key: &Key) -> Arc<AtomicU64> {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(key.name().to_owned())
+            .or_default()
+            .clone()
+    }
+
+    fn counter(&self, name: &str) -> u64 {
+        self.value(&Key::from_name(name.to_owned()))
+            .load(Ordering::SeqCst)
+    }
+
+    fn gauge(&self, name: &str) -> f64 {
+        f64::from_bits(
+            self.value(&Key::from_name(name.to_owned()))
+                .load(Ordering::SeqCst),
+        )
+    }
+}
+
+impl Recorder for &'static CapturingRecorder {
+    fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+        Counter::from_arc(self.value(key))
+    }
+
+    fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+        Gauge::from_arc(self.value(key))
+    }
+
+    fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+        Histogram::noop()
+    }
+}
+
+#[test]
+fn metrics() {
+    let recorder: &'static CapturingRecorder = Box::leak(Box::default());
+    metrics::set_global_recorder(recorder).unwrap();
+
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let _ = std::thread::spawn(|| panic!("first")).join();
+        let _ = std::thread::spawn(|| panic!("second")).join();
+        panic!("third")
+    });
+    assert!(result.is_none());
+
+    assert_eq!(recorder.counter("panik.panics_total"), 3);
+    assert_eq!(recorder.gauge("panik.backtraces_resolved"), 3.0);
+    assert_eq!(recorder.gauge("panik.backtraces_unresolved"), 0.0);
+}
@@ -0,0 +1,36 @@
+mod setup;
+
+use panik::NestedPolicy;
+
+#[test]
+fn nested_policy() {
+    let builder = setup::panik_builder();
+
+    let outer = builder.clone().run_and_handle_panics(|| {
+        let inner = panik::Builder::new()
+            .on_nested(NestedPolicy::ReturnNone)
+            .run_and_handle_panics(|| 5);
+        assert!(inner.is_none());
+
+        let inner = panik::Builder::new()
+            .on_nested(NestedPolicy::Transparent)
+            .run_and_handle_panics(|| 5);
+        assert_eq!(inner, Some(5));
+
+        0
+    });
+    assert_eq!(outer, Some(0));
+    assert!(!panik::has_panicked());
+
+    // panics in a transparent scope are handled by the outer scope
+    let outer = builder.run_and_handle_panics(|| {
+        panik::Builder::new()
+            .on_nested(NestedPolicy::Transparent)
+            .run_and_handle_panics(|| -> i32 { panic!("inner") })
+    });
+    assert!(outer.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(panics[0].message(), "inner");
+}
@@ -0,0 +1,29 @@
+mod setup;
+
+#[test]
+fn numeric_payload() {
+    struct Opaque;
+
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let _ = std::thread::spawn(|| std::panic::panic_any(42i32)).join();
+        let _ = std::thread::spawn(|| std::panic::panic_any(7u64)).join();
+        let _ = std::thread::spawn(|| std::panic::panic_any(Opaque)).join();
+    });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 3);
+
+    assert_eq!(panics[0].message(), "42");
+    assert_eq!(panics[0].payload_type_name(), Some("i32"));
+
+    assert_eq!(panics[1].message(), "7");
+    assert_eq!(panics[1].payload_type_name(), Some("u64"));
+
+    let unknown = format!(
+        "(unknown payload type {:?})",
+        std::any::TypeId::of::<Opaque>()
+    );
+    assert!(panics[2].message().ends_with(&unknown));
+    assert_eq!(panics[2].payload_type_name(), None);
+}
@@ -0,0 +1,31 @@
+mod setup;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[test]
+fn on_first_panic() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let first_message = Arc::new(std::sync::Mutex::new(String::new()));
+
+    let result = setup::panik_builder()
+        .on_first_panic({
+            let calls = calls.clone();
+            let first_message = first_message.clone();
+            move |panic| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                *first_message.lock().unwrap() = panic.message().to_owned();
+                assert!(panik::has_panicked());
+            }
+        })
+        .run_and_handle_panics(|| {
+            for msg in &["first", "second", "third"] {
+                let _ = std::thread::spawn(move || std::panic::panic_any(*msg)).join();
+            }
+        });
+    assert!(result.is_none());
+    assert_eq!(panik::panics().len(), 3);
+
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+    assert_eq!(*first_message.lock().unwrap(), "first");
+}
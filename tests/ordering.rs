@@ -0,0 +1,20 @@
+mod setup;
+
+#[test]
+fn ordering() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        for i in 0..3 {
+            let _ = std::thread::spawn(move || panic!("panic {}", i)).join();
+        }
+    });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    let mut sorted = panics.clone();
+    sorted.reverse();
+    sorted.sort();
+
+    assert_eq!(sorted, panics);
+    assert!(panics[0] < panics[1] && panics[1] < panics[2]);
+    assert!(panics.windows(2).all(|w| w[0].sequence() < w[1].sequence()));
+}
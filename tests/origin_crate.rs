@@ -0,0 +1,16 @@
+mod setup;
+
+#[inline(never)]
+fn panics_here() {
+    panic!("whose fault is this")
+}
+
+#[test]
+fn origin_crate() {
+    let result = setup::panik_builder().run_and_handle_panics(panics_here);
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(panics[0].origin_crate(), Some("origin_crate"));
+}
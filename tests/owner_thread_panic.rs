@@ -0,0 +1,22 @@
+mod setup;
+
+#[test]
+fn owner_thread_panic() {
+    let builder = setup::panik_builder();
+
+    let _ = builder.clone().run_and_handle_panics(|| {
+        let _ = std::thread::spawn(|| panic!("worker")).join();
+    });
+    assert!(panik::owner_thread_panic().is_none());
+
+    let result = builder.run_and_handle_panics(|| {
+        let _ = std::thread::spawn(|| panic!("worker")).join();
+        panic!("owner");
+    });
+    assert!(result.is_none());
+    assert_eq!(panik::panics().len(), 2);
+
+    let panic = panik::owner_thread_panic().unwrap();
+    assert_eq!(panic.message(), "owner");
+    assert_eq!(panic.thread_id(), std::thread::current().id());
+}
@@ -0,0 +1,30 @@
+mod setup;
+
+use std::time::Duration;
+
+#[test]
+fn panic_budget() {
+    let builder = setup::panik_builder().panic_budget(2, Duration::from_secs(60));
+
+    let within = builder.clone().run_and_handle_panics(|| {
+        for _ in 0..2 {
+            let _ = std::thread::spawn(|| panic!("isolated")).join();
+        }
+        assert!(!panik::has_panicked());
+        "fine"
+    });
+    assert_eq!(within, Some("fine"));
+    assert!(!panik::budget_exceeded());
+    assert_eq!(panik::panics().len(), 2);
+
+    let exceeded = builder.run_and_handle_panics(|| {
+        for _ in 0..3 {
+            let _ = std::thread::spawn(|| panic!("storm")).join();
+        }
+        "fine"
+    });
+    assert!(exceeded.is_none());
+    assert!(panik::budget_exceeded());
+    assert!(panik::has_panicked());
+    assert_eq!(panik::panics().len(), 3);
+}
@@ -0,0 +1,32 @@
+mod setup;
+
+use std::error::Error;
+
+#[test]
+fn panic_error() {
+    let builder = setup::panik_builder();
+
+    let result = builder.clone().run_and_handle_panics_result(|| 5);
+    assert_eq!(result.unwrap(), 5);
+
+    let err = builder
+        .clone()
+        .run_and_handle_panics_result(|| {
+            let _ = std::thread::spawn(|| panic!("first")).join();
+            panic!("second")
+        })
+        .unwrap_err();
+    assert_eq!(err.to_string(), "2 threads panicked, first: first");
+    assert_eq!(err.panics().len(), 2);
+
+    let boxed: Box<dyn Error> = Box::new(err);
+    assert!(boxed.source().is_none());
+
+    // the closure's own panic is suppressed, so there are none to return
+    let err = builder
+        .suppress_messages(vec!["benign".to_owned()])
+        .run_and_handle_panics_result(|| panic!("benign"))
+        .unwrap_err();
+    assert!(err.panics().is_empty());
+    assert_eq!(err.to_string(), "scope panicked, but no panics were stored");
+}
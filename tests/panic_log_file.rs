@@ -0,0 +1,45 @@
+mod setup;
+
+#[test]
+fn panic_log_file() {
+    let path = std::env::temp_dir().join(format!("panik-log-{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let build_info = panik::BuildInfo {
+        version: "1.2.3".to_owned(),
+        git_sha: "abc123".to_owned(),
+        build_timestamp: "2024-01-01".to_owned(),
+    };
+    let result = setup::panik_builder()
+        .panic_log_file(&path)
+        .build_info(build_info.clone())
+        .run_and_handle_panics(|| {
+            let threads = (0..4)
+                .map(|_| std::thread::spawn(|| std::panic::panic_any("tab\there\nnewline")))
+                .collect::<Vec<_>>();
+            for thread in threads {
+                let _ = thread.join();
+            }
+        });
+    assert!(result.is_none());
+
+    let read = panik::read_panics_from_file(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let panics = panik::panics();
+    assert_eq!(read.len(), panics.len());
+    assert_eq!(read.len(), 4);
+
+    for lite in &read {
+        let panic = panics
+            .iter()
+            .find(|p| p.sequence() == lite.sequence())
+            .unwrap();
+        assert_eq!(lite.process_id(), std::process::id());
+        assert_eq!(lite.message(), "tab\there\nnewline");
+        assert_eq!(lite.thread_name(), panic.thread_name());
+        assert_eq!(lite.location(), panic.location());
+        assert_eq!(lite.build_info(), Some(&build_info));
+        assert!(lite.env_vars().is_empty());
+    }
+}
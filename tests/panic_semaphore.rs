@@ -0,0 +1,28 @@
+mod setup;
+
+use std::time::Duration;
+
+#[test]
+fn panic_semaphore() {
+    let semaphore = panik::PanicSemaphore::new();
+    let result = setup::panik_builder()
+        .panic_semaphore(semaphore.clone())
+        .run_and_handle_panics(|| {
+            assert!(!semaphore.try_acquire());
+            assert!(!semaphore.acquire_timeout(Duration::from_millis(10)));
+
+            let workers = (0..2)
+                .map(|_| std::thread::spawn(|| panic!("oh no")))
+                .collect::<Vec<_>>();
+            semaphore.acquire();
+            for worker in workers {
+                let _ = worker.join();
+            }
+
+            // one permit per panic
+            assert_eq!(semaphore.available_permits(), 1);
+            assert!(semaphore.try_acquire());
+            assert!(!semaphore.try_acquire());
+        });
+    assert!(result.is_none());
+}
@@ -0,0 +1,21 @@
+#![cfg(feature = "use-tokio")]
+
+mod setup;
+
+use futures_util::StreamExt;
+
+#[tokio::test]
+async fn panic_stream() {
+    let mut stream_a = Box::pin(panik::panic_stream());
+    let mut stream_b = Box::pin(panik::panic_stream());
+
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let _ = std::thread::spawn(|| panic!("streamed")).join();
+    });
+    assert!(result.is_none());
+
+    let a = stream_a.next().await.unwrap();
+    let b = stream_b.next().await.unwrap();
+    assert_eq!(a.message(), "streamed");
+    assert_eq!(b.message(), "streamed");
+}
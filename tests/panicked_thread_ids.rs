@@ -0,0 +1,26 @@
+mod setup;
+
+use std::sync::Mutex;
+
+#[test]
+fn panicked_thread_ids() {
+    let failed = Mutex::new(Vec::new());
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        for i in 0..6 {
+            let worker = std::thread::spawn(move || {
+                if i % 2 == 0 {
+                    panic!("worker {} failed", i);
+                }
+            });
+            let tid = worker.thread().id();
+            if worker.join().is_err() {
+                failed.lock().unwrap().push(tid);
+            }
+        }
+    });
+    assert!(result.is_none());
+
+    let failed = failed.into_inner().unwrap();
+    assert_eq!(failed.len(), 3);
+    assert_eq!(panik::panicked_thread_ids(), failed);
+}
@@ -0,0 +1,41 @@
+#![cfg(feature = "use-log")]
+
+struct PanickingLogger;
+
+impl log::Log for PanickingLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        // only the report logged during resolution, not the line logged from the panic hook
+        let msg = record.args().to_string();
+        if msg.starts_with("panic on thread") && msg.contains("log sink victim") {
+            panic!("log sink exploded");
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn panicking_log_sink() {
+    log::set_logger(&PanickingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let result =
+        panik::Builder::new().run_and_handle_panics(|| std::panic::panic_any("log sink victim"));
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(panics[0].message(), "log sink victim");
+
+    // replaying them hits the same sink, but doesn't deadlock or register another panic
+    panik::log_all_panics();
+    assert_eq!(panik::panics(), panics);
+
+    // state is still usable afterwards
+    let result = panik::Builder::new().run_and_handle_panics(|| 5);
+    assert_eq!(result, Some(5));
+}
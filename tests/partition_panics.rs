@@ -0,0 +1,33 @@
+mod setup;
+
+#[test]
+fn partition_panics() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let worker = std::thread::spawn(|| {
+            let _ = std::thread::spawn(|| panic!("nested")).join();
+            panic!("worker")
+        });
+        let _ = worker.join();
+    });
+    assert!(result.is_none());
+    assert_eq!(panik::panics().len(), 2);
+
+    let worker_id = panik::panics()
+        .into_iter()
+        .find(|p| p.message() == "worker")
+        .unwrap()
+        .thread_id();
+
+    let drained = panik::partition_panics(|p| p.thread_id() == worker_id);
+    assert_eq!(drained.len(), 1);
+    assert_eq!(drained[0].message(), "worker");
+
+    let remaining = panik::panics();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].message(), "nested");
+    assert!(panik::has_panicked());
+
+    let drained = panik::partition_panics(|_| true);
+    assert_eq!(drained.len(), 1);
+    assert!(!panik::has_panicked());
+}
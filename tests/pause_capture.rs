@@ -0,0 +1,31 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+mod setup;
+
+static PREVIOUS_HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn pause_capture() {
+    std::panic::set_hook(Box::new(|_| {
+        PREVIOUS_HOOK_CALLS.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        panik::pause_capture();
+        let _ = std::thread::spawn(|| panic!("ignored")).join();
+        assert!(!panik::has_panicked());
+
+        // handled by the previous hook instead
+        assert_eq!(PREVIOUS_HOOK_CALLS.load(Ordering::SeqCst), 1);
+
+        panik::resume_capture();
+        let _ = std::thread::spawn(|| panic!("captured")).join();
+        assert!(panik::has_panicked());
+    });
+    assert!(result.is_none());
+    assert_eq!(PREVIOUS_HOOK_CALLS.load(Ordering::SeqCst), 1);
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(panics[0].message(), "captured");
+}
@@ -0,0 +1,45 @@
+#![cfg(target_os = "linux")]
+
+mod setup;
+
+#[test]
+fn persistent_ring() {
+    let path = std::env::temp_dir().join(format!("panik-ring-{}.bin", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let build_info = panik::BuildInfo {
+        version: "1.2.3".to_owned(),
+        git_sha: "abc123".to_owned(),
+        build_timestamp: "2024-01-01".to_owned(),
+    };
+    let builder = setup::panik_builder()
+        .persistent_ring(&path, 2)
+        .build_info(build_info.clone());
+    let long: &'static str = Box::leak("x".repeat(1000).into_boxed_str());
+
+    for message in ["one", "two", long] {
+        let result = builder
+            .clone()
+            .run_and_handle_panics(move || std::panic::panic_any(message));
+        assert!(result.is_none());
+    }
+
+    // only the most recent panics are kept, truncated to fit
+    let read = panik::read_persistent_ring(&path).unwrap();
+    assert_eq!(read.len(), 2);
+    assert_eq!(read[0].message(), "two");
+    assert!(read[1].message().len() < long.len());
+    assert!(long.starts_with(read[1].message()));
+    assert!(read.iter().all(|p| p.build_info() == Some(&build_info)));
+
+    // corrupt the oldest record, which is skipped
+    let mut bytes = std::fs::read(&path).unwrap();
+    let record_start = bytes.windows(3).position(|w| w == b"two").unwrap();
+    bytes[record_start] = b'T';
+    std::fs::write(&path, &bytes).unwrap();
+
+    let read = panik::read_persistent_ring(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(read.len(), 1);
+    assert!(long.starts_with(read[0].message()));
+}
@@ -0,0 +1,27 @@
+mod setup;
+
+use backtrace::Backtrace;
+
+#[test]
+fn possibly_ffi() {
+    let builder = setup::panik_builder();
+    let result = builder.clone().run_and_handle_panics(|| {
+        let _ = std::thread::spawn(|| panic!("plain rust")).join();
+        panic!("also plain rust")
+    });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 2);
+    assert!(panics.iter().all(|p| !p.possibly_ffi()));
+
+    // a custom capturer capping the depth isn't mistaken for FFI
+    let result = builder
+        .backtrace_capturer(|| {
+            let frames = Backtrace::new_unresolved().frames()[..3].to_vec();
+            Backtrace::from(frames)
+        })
+        .run_and_handle_panics(|| panic!("shallow"));
+    assert!(result.is_none());
+    assert!(!panik::panics()[0].possibly_ffi());
+}
@@ -0,0 +1,26 @@
+mod setup;
+
+#[test]
+fn preserve_panics() {
+    let builder = setup::panik_builder().preserve_panics_across_scopes(true);
+
+    let a = builder
+        .clone()
+        .run_and_handle_panics(|| panic!("numero one"));
+    assert!(a.is_none());
+
+    // no new panics, so the result is not swallowed
+    let b = builder.clone().run_and_handle_panics(|| 1);
+    assert_eq!(b, Some(1));
+    assert!(panik::has_panicked());
+    assert_eq!(panik::panics().len(), 1);
+
+    let c = builder.run_and_handle_panics(|| panic!("numero two"));
+    assert!(c.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 2);
+    assert_eq!(panics[0].message(), "numero one");
+    assert_eq!(panics[1].message(), "numero two");
+    assert!(panics.iter().all(|p| p.is_backtrace_resolved()));
+}
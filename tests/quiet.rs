@@ -0,0 +1,36 @@
+#![cfg(feature = "use-log")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static RECORDS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingLogger;
+
+impl log::Log for CountingLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, _: &log::Record) {
+        RECORDS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn quiet() {
+    log::set_logger(&CountingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let result = panik::Builder::new()
+        .quiet(true)
+        .run_and_handle_panics(|| panic!("shh"));
+    assert!(result.is_none());
+    assert_eq!(panik::panics().len(), 1);
+    assert_eq!(RECORDS.load(Ordering::Relaxed), 0);
+
+    let result = panik::Builder::new().run_and_handle_panics(|| panic!("loud"));
+    assert!(result.is_none());
+    assert!(RECORDS.load(Ordering::Relaxed) > 0);
+}
@@ -0,0 +1,30 @@
+mod setup;
+
+#[test]
+fn raw() {
+    let builder = setup::panik_builder();
+
+    let _ = builder.clone().run_and_handle_panics(|| {
+        let _ = std::thread::spawn(|| std::panic::panic_any(format!("owned {}", 123))).join();
+        let _ = std::thread::spawn(|| std::panic::panic_any(5_i32)).join();
+    });
+    let panics = panik::panics();
+
+    let raw = panics[0].raw();
+    assert_eq!(raw.message(), Some("owned 123"));
+    assert_ne!(panics[0].message(), "owned 123");
+    assert_eq!(raw.thread_id(), panics[0].thread_id());
+    let (file, line, _) = raw.location().unwrap();
+    assert!(file.ends_with("raw.rs"));
+    assert_eq!(line, 8);
+
+    // not a string payload
+    assert_eq!(panics[1].raw().message(), None);
+
+    let _ = builder
+        .max_message_len(4)
+        .run_and_handle_panics(|| panic!("a long message"));
+    let panic = &panik::panics()[0];
+    assert_eq!(panic.message(), "a lo…(truncated)");
+    assert_eq!(panic.raw().message(), Some("a lo"));
+}
@@ -0,0 +1,45 @@
+mod setup;
+
+use panik::Registry;
+
+#[test]
+fn registry() {
+    let audio = Registry::new("audio");
+    let network = Registry::new("network");
+
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let audio = audio.clone();
+        let _ = std::thread::spawn(move || {
+            audio.register_current_thread();
+            panic!("audio device lost")
+        })
+        .join();
+
+        "still running"
+    });
+    assert_eq!(result, Some("still running"));
+    assert!(!panik::has_panicked());
+
+    assert_eq!(audio.name(), "audio");
+    assert!(audio.has_panicked());
+    assert!(!network.has_panicked());
+
+    let audio_panics = audio.panics();
+    assert_eq!(audio_panics.len(), 1);
+    assert_eq!(audio_panics[0].message(), "audio device lost");
+
+    // unregistered threads fall back to the global set
+    let result = panik::run_and_handle_panics(|| {
+        let network = network.clone();
+        let _ = std::thread::spawn(move || {
+            let tid = std::thread::current().id();
+            network.register_thread(tid);
+            network.unregister_thread(tid);
+            panic!("global")
+        })
+        .join();
+    });
+    assert!(result.is_none());
+    assert!(!network.has_panicked());
+    assert_eq!(panik::panics()[0].message(), "global");
+}
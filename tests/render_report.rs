@@ -0,0 +1,21 @@
+mod setup;
+
+#[test]
+fn render_report() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let _ = std::thread::spawn(|| panic!("first")).join();
+        panic!("second")
+    });
+    assert!(result.is_none());
+
+    let report = panik::render_report();
+    assert!(report.starts_with("2 threads panicked\n"));
+    assert!(report.contains("[1/2] panic on thread"));
+    assert!(report.contains("\"first\" at tests/render_report.rs:6:"));
+    assert!(report.contains("[2/2] panic on thread"));
+    assert!(report.contains("\"second\" at tests/render_report.rs:7:"));
+
+    let location = panik::panics()[1].location().cloned().unwrap();
+    assert_eq!(location.file(), "tests/render_report.rs");
+    assert_eq!(location.line(), 7);
+}
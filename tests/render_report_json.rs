@@ -0,0 +1,39 @@
+#![cfg(feature = "use-serde")]
+
+mod setup;
+
+#[test]
+fn render_report_json() {
+    let builder = setup::panik_builder();
+
+    let result = builder.clone().run_and_handle_panics(|| 5);
+    assert_eq!(result, Some(5));
+
+    let empty: serde_json::Value = serde_json::from_str(&panik::render_report_json()).unwrap();
+    assert_eq!(empty["count"], 0);
+    assert_eq!(empty["panics"].as_array().unwrap().len(), 0);
+    assert!(empty.get("diagnostics").is_none());
+
+    let result = builder
+        .backtrace_resolution_limit(1)
+        .build_info(panik::BuildInfo {
+            version: "1.2.3".to_owned(),
+            ..Default::default()
+        })
+        .run_and_handle_panics(|| {
+            let _ = std::thread::spawn(|| panic!("unresolved")).join();
+            panic!("resolved")
+        });
+    assert!(result.is_none());
+
+    let report: serde_json::Value = serde_json::from_str(&panik::render_report_json()).unwrap();
+    assert_eq!(report["count"], 2);
+    assert_eq!(report["diagnostics"]["build"]["version"], "1.2.3");
+
+    let panics = report["panics"].as_array().unwrap();
+    assert_eq!(panics[0]["message"], "unresolved");
+    assert!(panics[0]["backtrace"].is_null());
+    assert_eq!(panics[1]["message"], "resolved");
+    assert!(panics[1]["backtrace"].is_string());
+    assert_eq!(panics[1]["location"]["file"], "tests/render_report_json.rs");
+}
@@ -0,0 +1,19 @@
+mod setup;
+
+#[test]
+fn require_handled() {
+    let builder = setup::panik_builder().require_handled(true);
+
+    let result = builder.clone().run_and_handle_panics(|| panic!("handled"));
+    assert!(result.is_none());
+    assert_eq!(panik::take_panics().len(), 1);
+    assert!(panik::panics().is_empty());
+    assert!(!panik::has_panicked());
+
+    // forgotten, only warns when the next scope begins
+    let result = builder.run_and_handle_panics(|| panic!("forgotten"));
+    assert!(result.is_none());
+
+    let result = panik::run_and_handle_panics(|| 5);
+    assert_eq!(result, Some(5));
+}
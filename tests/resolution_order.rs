@@ -0,0 +1,26 @@
+mod setup;
+
+#[test]
+fn resolution_order() {
+    let result = setup::panik_builder()
+        .backtrace_resolution_limit(1)
+        .run_and_handle_panics(|| {
+            for _ in 0..3 {
+                let _ = std::thread::spawn(|| panic!("worker")).join();
+            }
+
+            panic!("main")
+        });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 4);
+
+    let resolved = panics
+        .iter()
+        .filter(|p| p.is_backtrace_resolved())
+        .collect::<Vec<_>>();
+    assert_eq!(resolved.len(), 1);
+    assert_eq!(resolved[0].message(), "main");
+    assert_eq!(resolved[0].thread_id(), std::thread::current().id());
+}
@@ -0,0 +1,21 @@
+mod setup;
+
+use std::time::Duration;
+
+#[test]
+fn resolution_time_budget() {
+    let builder = setup::panik_builder();
+
+    let result = builder
+        .clone()
+        .backtrace_resolution_time_budget(Duration::ZERO)
+        .run_and_handle_panics(|| panic!("no time"));
+    assert!(result.is_none());
+    assert!(!panik::panics()[0].is_backtrace_resolved());
+
+    let result = builder
+        .backtrace_resolution_time_budget(Duration::from_secs(60))
+        .run_and_handle_panics(|| panic!("plenty of time"));
+    assert!(result.is_none());
+    assert!(panik::panics()[0].is_backtrace_resolved());
+}
@@ -0,0 +1,26 @@
+mod setup;
+
+#[test]
+fn restart_classifier() {
+    let builder = setup::panik_builder();
+
+    let _ = builder
+        .clone()
+        .run_and_handle_panics(|| panic!("connection reset"));
+    assert!(!panik::panics()[0].restart_suggested());
+
+    let result = builder
+        .restart_classifier(|panic| panic.message().starts_with("io error"))
+        .run_and_handle_panics(|| {
+            for msg in &["io error: connection reset", "assertion failed: x == y"] {
+                let _ = std::thread::spawn(move || std::panic::panic_any(*msg)).join();
+            }
+        });
+    assert!(result.is_none());
+
+    let suggested: Vec<_> = panik::panics()
+        .iter()
+        .map(|panic| panic.restart_suggested())
+        .collect();
+    assert_eq!(suggested, [true, false]);
+}
@@ -0,0 +1,38 @@
+mod setup;
+
+#[derive(Clone, Debug, PartialEq)]
+struct MyError {
+    code: u32,
+}
+
+struct Unclonable(std::sync::Mutex<u32>);
+
+#[test]
+fn retain_payload() {
+    let builder = setup::panik_builder().retain_payload(true);
+
+    let result = builder
+        .clone()
+        .run_and_handle_panics(|| std::panic::panic_any(MyError { code: 42 }));
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(
+        panics[0].downcast_payload::<MyError>(),
+        Some(MyError { code: 42 })
+    );
+    assert_eq!(panics[0].downcast_payload::<String>(), None);
+
+    let result = builder
+        .run_and_handle_panics(|| std::panic::panic_any(Unclonable(std::sync::Mutex::new(7))));
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(
+        panics[0].with_payload(|p: &Unclonable| *p.0.lock().unwrap()),
+        Some(7)
+    );
+    assert_eq!(panics[0].with_payload(|p: &MyError| p.code), None);
+}
@@ -0,0 +1,26 @@
+mod setup;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn retry() {
+    let builder = setup::panik_builder().retry(3);
+    let calls = AtomicUsize::new(0);
+
+    let result = builder.clone().run_and_handle_panics_with_retry(|| {
+        if calls.fetch_add(1, Ordering::Relaxed) == 0 {
+            panic!("first time unlucky");
+        }
+
+        "second time lucky"
+    });
+
+    assert_eq!(result, Some("second time lucky"));
+    assert_eq!(panik::last_attempt_count(), 2);
+    assert!(!panik::has_panicked());
+
+    let result = builder.run_and_handle_panics_with_retry(|| -> i32 { panic!("never lucky") });
+    assert!(result.is_none());
+    assert_eq!(panik::last_attempt_count(), 3);
+    assert_eq!(panik::panics().len(), 1);
+}
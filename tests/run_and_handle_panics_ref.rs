@@ -0,0 +1,26 @@
+mod setup;
+
+#[test]
+fn run_and_handle_panics_ref() {
+    let builder = setup::panik_builder().scope_name("phase");
+
+    for phase in 0..3 {
+        let result = builder.run_and_handle_panics_ref(|| {
+            if phase == 1 {
+                panic!("phase one failed");
+            }
+            phase
+        });
+
+        if phase == 1 {
+            assert!(result.is_none());
+            let panics = panik::panics();
+            assert_eq!(panics.len(), 1);
+            assert_eq!(panics[0].message(), "phase one failed");
+            assert_eq!(panics[0].captured_by(), Some("phase"));
+        } else {
+            assert_eq!(result, Some(phase));
+            assert!(!panik::has_panicked());
+        }
+    }
+}
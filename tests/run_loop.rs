@@ -0,0 +1,26 @@
+mod setup;
+
+use std::ops::ControlFlow;
+
+#[test]
+fn run_loop() {
+    let _ = setup::panik_builder();
+
+    let mut ticks = 0;
+    let result = panik::run_loop_until_panic(|| {
+        ticks += 1;
+        if ticks == 5 {
+            ControlFlow::Break(ticks)
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    assert_eq!(result, Some(5));
+
+    let result = panik::run_loop_until_panic(|| {
+        let _ = std::thread::spawn(|| panic!("oh no")).join();
+        ControlFlow::<()>::Continue(())
+    });
+    assert!(result.is_none());
+    assert_eq!(panik::panics().len(), 1);
+}
@@ -0,0 +1,17 @@
+mod setup;
+
+#[test]
+fn scope_name() {
+    let builder = setup::panik_builder();
+
+    let result = builder
+        .clone()
+        .scope_name("renderer")
+        .run_and_handle_panics(|| panic!("named"));
+    assert!(result.is_none());
+    assert_eq!(panik::panics()[0].captured_by(), Some("renderer"));
+
+    let result = builder.run_and_handle_panics(|| panic!("unnamed"));
+    assert!(result.is_none());
+    assert_eq!(panik::panics()[0].captured_by(), None);
+}
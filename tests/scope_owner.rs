@@ -0,0 +1,16 @@
+mod setup;
+
+#[test]
+fn scope_owner() {
+    assert!(!panik::is_scope_owner_thread());
+
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let other = std::thread::spawn(panik::is_scope_owner_thread)
+            .join()
+            .unwrap();
+        (panik::is_scope_owner_thread(), other)
+    });
+    assert_eq!(result, Some((true, false)));
+
+    assert!(!panik::is_scope_owner_thread());
+}
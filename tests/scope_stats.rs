@@ -0,0 +1,25 @@
+mod setup;
+
+#[test]
+fn scope_stats() {
+    let builder = setup::panik_builder()
+        .scope_history_len(2)
+        .backtrace_resolution_limit(1);
+
+    let _ = builder.clone().run_and_handle_panics(|| panic!("one"));
+    let _ = builder.clone().run_and_handle_panics(|| 2);
+    let _ = builder.run_and_handle_panics(|| {
+        for _ in 0..3 {
+            let _ = std::thread::spawn(|| panic!("uh oh")).join();
+        }
+    });
+
+    let stats = panik::recent_scope_stats();
+    assert_eq!(stats.len(), 2);
+
+    assert_eq!(stats[0].panic_count(), 0);
+    assert_eq!(stats[0].resolved_count(), 0);
+
+    assert_eq!(stats[1].panic_count(), 3);
+    assert_eq!(stats[1].resolved_count(), 1);
+}
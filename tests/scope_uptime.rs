@@ -0,0 +1,16 @@
+mod setup;
+
+use std::time::Duration;
+
+#[test]
+fn scope_uptime() {
+    assert!(panik::scope_uptime().is_none());
+
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        std::thread::sleep(Duration::from_millis(20));
+        panik::scope_uptime()
+    });
+    assert!(result.unwrap().unwrap() >= Duration::from_millis(20));
+
+    assert!(panik::scope_uptime().is_none());
+}
@@ -13,11 +13,14 @@ pub fn panik_builder() -> panik::Builder {
         let log = slog::Logger::root(slog_term::FullFormat::new(plain).build().fuse(), slog_o!());
         let guard = slog_scope::set_global_logger(log);
 
-        let builder = panik::Builder::default().slogger(slog_scope::logger());
+        let builder = panik::Builder::default()
+            .slogger(slog_scope::logger())
+            .capture_backtraces(true);
         std::mem::forget(guard);
 
         return builder;
     }
 
-    panik::Builder::default()
+    // tests inspect backtraces regardless of RUST_BACKTRACE
+    panik::Builder::default().capture_backtraces(true)
 }
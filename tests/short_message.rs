@@ -0,0 +1,21 @@
+mod setup;
+
+#[test]
+fn short_message() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let _ = std::thread::spawn(|| {
+            std::panic::panic_any("assertion `left == right` failed\n  left: 1\n right: 2")
+        })
+        .join();
+        let _ = std::thread::spawn(|| std::panic::panic_any("single line")).join();
+    });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(
+        panics[0].short_message(),
+        "assertion `left == right` failed"
+    );
+    assert!(panics[0].message().contains("right: 2"));
+    assert_eq!(panics[1].short_message(), "single line");
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "use-slog")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+struct CountingDrain(Arc<AtomicUsize>);
+
+impl slog::Drain for CountingDrain {
+    type Ok = ();
+    type Err = slog::Never;
+
+    fn log(&self, _: &slog::Record, _: &slog::OwnedKVList) -> Result<(), slog::Never> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[test]
+fn slogger_persists() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let slogger = slog::Logger::root(CountingDrain(count.clone()), slog::o!());
+
+    let a = panik::Builder::new()
+        .slogger(slogger)
+        .run_and_handle_panics(|| panic!("numero one"));
+    assert!(a.is_none());
+
+    let after_first = count.load(Ordering::Relaxed);
+    assert!(after_first > 0);
+
+    // slogger is not passed again
+    let b = panik::run_and_handle_panics(|| panic!("numero two"));
+    assert!(b.is_none());
+    assert!(count.load(Ordering::Relaxed) > after_first);
+}
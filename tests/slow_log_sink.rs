@@ -0,0 +1,61 @@
+#![cfg(feature = "use-log")]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const SLOW_LOG: Duration = Duration::from_millis(500);
+
+struct SlowLogger;
+
+impl log::Log for SlowLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, _: &log::Record) {
+        std::thread::sleep(SLOW_LOG);
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn slow_log_sink() {
+    log::set_logger(&SlowLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let checker = {
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            let mut slowest = Duration::ZERO;
+            while !stop.load(Ordering::Relaxed) {
+                let start = Instant::now();
+                // takes the lock, unlike has_panicked
+                let _ = panik::panics();
+                slowest = slowest.max(start.elapsed());
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            slowest
+        })
+    };
+
+    let started = Instant::now();
+    // resolving backtraces also takes the lock, so they're skipped to only measure logging
+    let builder = panik::Builder::new().capture_backtraces(false);
+    let result = builder
+        .clone()
+        .run_and_handle_panics(|| std::panic::panic_any("slow"));
+    assert!(result.is_none());
+    assert!(started.elapsed() >= SLOW_LOG);
+
+    let result = builder.soft_panic_threshold(2).run_and_handle_panics(|| {
+        let _ = std::thread::spawn(|| std::panic::panic_any("tolerated")).join();
+    });
+    assert!(result.is_some());
+
+    stop.store(true, Ordering::Relaxed);
+    let slowest = checker.join().unwrap();
+    assert!(slowest < SLOW_LOG / 2, "panics blocked for {:?}", slowest);
+}
@@ -0,0 +1,25 @@
+mod setup;
+
+#[test]
+fn snapshot() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        for _ in 0..2 {
+            let _ = std::thread::spawn(|| panic!("oh no")).join();
+        }
+    });
+    assert!(result.is_none());
+
+    let snapshot = panik::snapshot();
+    assert_eq!(snapshot.len(), 2);
+
+    for panic in &snapshot {
+        // doesn't deadlock
+        assert!(panik::has_thread_panicked(panic.thread_id()));
+    }
+
+    panik::clear_panics_matching(|_| true);
+    assert!(panik::panics().is_empty());
+
+    let owned = snapshot.into_iter().collect::<Vec<_>>();
+    assert_eq!(owned.len(), 2);
+}
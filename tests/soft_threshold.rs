@@ -0,0 +1,33 @@
+mod setup;
+
+use panik::Severity;
+
+#[test]
+fn soft_threshold() {
+    let builder = setup::panik_builder().soft_panic_threshold(3);
+
+    let tolerated = builder.clone().run_and_handle_panics(|| {
+        for _ in 0..2 {
+            let _ = std::thread::spawn(|| panic!("retryable")).join();
+        }
+        "ok"
+    });
+    assert_eq!(tolerated, Some("ok"));
+    assert!(panik::has_panicked());
+
+    let summary = panik::last_scope_summary().unwrap();
+    assert_eq!(summary.panic_count(), 2);
+    assert_eq!(summary.severity(), Severity::Soft);
+
+    let fatal = builder.run_and_handle_panics(|| {
+        for _ in 0..3 {
+            let _ = std::thread::spawn(|| panic!("retryable")).join();
+        }
+        "ok"
+    });
+    assert!(fatal.is_none());
+
+    let summary = panik::last_scope_summary().unwrap();
+    assert_eq!(summary.panic_count(), 3);
+    assert_eq!(summary.severity(), Severity::Fatal);
+}
@@ -0,0 +1,22 @@
+mod setup;
+
+#[test]
+fn spawn_backtrace() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let registered = std::thread::spawn(|| {
+            panik::register_current_thread_spawn_site();
+            panic!("registered")
+        });
+        let unregistered = std::thread::spawn(|| panic!("unregistered"));
+        let _ = registered.join();
+        let _ = unregistered.join();
+    });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 2);
+    for panic in &panics {
+        let registered = panic.message() == "registered";
+        assert_eq!(panic.spawn_backtrace().is_some(), registered);
+    }
+}
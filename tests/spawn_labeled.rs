@@ -0,0 +1,16 @@
+mod setup;
+
+#[test]
+fn spawn_labeled() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let worker = panik::spawn_labeled("labeled-worker", || -> u32 { panic!("oh no") });
+        assert!(worker.join().is_err());
+
+        panik::spawn_labeled("happy-worker", || 5).join().unwrap()
+    });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert!(panics[0].thread_name().ends_with("(labeled-worker)"));
+}
@@ -0,0 +1,34 @@
+#![cfg(not(any(feature = "use-log", feature = "use-slog", feature = "use-stderr")))]
+
+use std::process::Command;
+
+const CHILD_ENV: &str = "PANIK_STDERR_FALLBACK";
+
+/// Runs this same test in a child process to capture what it prints to stderr.
+fn child_stderr(fallback: bool) -> String {
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "stderr_fallback", "--nocapture"])
+        .env(CHILD_ENV, fallback.to_string())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn stderr_fallback() {
+    if let Ok(fallback) = std::env::var(CHILD_ENV) {
+        let result = panik::Builder::new()
+            .default_stderr_when_no_feature(fallback == "true")
+            .run_and_handle_panics(|| panic!("oh no"));
+        assert!(result.is_none());
+        return;
+    }
+
+    let stderr = child_stderr(true);
+    assert!(stderr.contains("panic on thread"));
+    assert!(stderr.contains("\"oh no\""));
+
+    let stderr = child_stderr(false);
+    assert!(!stderr.contains("panic on thread"));
+}
@@ -0,0 +1,28 @@
+mod setup;
+
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static FAILED_WITH: AtomicUsize = AtomicUsize::new(0);
+
+struct Exited;
+
+#[test]
+fn strict() {
+    let builder = setup::panik_builder().failure_action(|panics| {
+        FAILED_WITH.store(panics.len(), Ordering::Relaxed);
+
+        // bail out before the process exits
+        std::panic::resume_unwind(Box::new(Exited));
+    });
+
+    let result = builder.clone().run_and_handle_panics_strict(|| 5);
+    assert_eq!(result, 5);
+    assert_eq!(FAILED_WITH.load(Ordering::Relaxed), 0);
+
+    let exited = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        builder.run_and_handle_panics_strict(|| -> i32 { panic!("oh no") })
+    }));
+    assert!(exited.unwrap_err().is::<Exited>());
+    assert_eq!(FAILED_WITH.load(Ordering::Relaxed), 1);
+}
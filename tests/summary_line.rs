@@ -0,0 +1,19 @@
+mod setup;
+
+#[test]
+fn summary_line() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        std::thread::Builder::new()
+            .name("summariser".to_owned())
+            .spawn(|| panic!("first\nsecond"))
+            .unwrap()
+            .join()
+    });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    let line = panics[0].summary_line();
+    assert!(!line.contains('\n'));
+    assert!(line.starts_with("thread summariser (ThreadId("));
+    assert!(line.contains("panicked: first\\nsecond at tests/summary_line.rs:"));
+}
@@ -0,0 +1,27 @@
+mod setup;
+
+#[test]
+fn suppress_messages() {
+    let path = std::env::temp_dir().join(format!("panik-suppress-{}.txt", std::process::id()));
+    std::fs::write(&path, "noisy from file\n\n").unwrap();
+    std::env::set_var("PANIK_TEST_SUPPRESS", &path);
+
+    let result = setup::panik_builder()
+        .suppress_messages(vec!["benign".to_owned()])
+        .suppress_messages_from_env("PANIK_TEST_SUPPRESS")
+        .run_and_handle_panics(|| {
+            let _ = std::thread::spawn(|| std::panic::panic_any("known benign issue")).join();
+            let _ = std::thread::spawn(|| std::panic::panic_any("noisy from file")).join();
+            "ok"
+        });
+    assert_eq!(result, Some("ok"));
+    assert!(!panik::has_panicked());
+
+    let result = panik::run_and_handle_panics(|| {
+        let _ = std::thread::spawn(|| std::panic::panic_any("benign but not suppressed")).join();
+    });
+    assert!(result.is_none());
+    assert_eq!(panik::panics().len(), 1);
+
+    let _ = std::fs::remove_file(&path);
+}
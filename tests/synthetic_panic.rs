@@ -0,0 +1,21 @@
+mod setup;
+
+#[test]
+fn synthetic_panic() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        std::thread::spawn(|| panik::record_synthetic_panic("device lost"))
+            .join()
+            .unwrap();
+
+        assert!(panik::has_panicked());
+        5
+    });
+
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(panics[0].message(), "device lost");
+    assert_ne!(panics[0].thread_id(), std::thread::current().id());
+    assert!(panics[0].backtrace().frames().is_empty());
+}
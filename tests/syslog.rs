@@ -0,0 +1,11 @@
+#![cfg(all(feature = "use-syslog", unix))]
+
+mod setup;
+
+#[test]
+fn syslog() {
+    // logging to syslog must not interfere with handling, even if there's no syslog daemon
+    let result = setup::panik_builder().run_and_handle_panics(|| panic!("to syslog"));
+    assert!(result.is_none());
+    assert_eq!(panik::panics().len(), 1);
+}
@@ -0,0 +1,33 @@
+use std::time::{Duration, Instant};
+
+mod setup;
+
+#[test]
+fn thread_cpu_time() {
+    let builder = setup::panik_builder();
+
+    let _ = builder
+        .clone()
+        .run_and_handle_panics(|| panic!("not captured"));
+    assert_eq!(panik::panics()[0].thread_cpu_time(), None);
+
+    let result = builder.capture_cpu_time(true).run_and_handle_panics(|| {
+        std::thread::spawn(|| {
+            // spin to accumulate some cpu time
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_millis(50) {
+                std::hint::spin_loop();
+            }
+            panic!("hot loop");
+        })
+        .join()
+    });
+    assert!(result.is_none());
+
+    let cpu_time = panik::panics()[0].thread_cpu_time();
+    if cfg!(target_os = "linux") {
+        assert!(cpu_time.unwrap() >= Duration::from_millis(10));
+    } else {
+        assert_eq!(cpu_time, None);
+    }
+}
@@ -0,0 +1,31 @@
+mod setup;
+
+#[test]
+fn thread_describer() {
+    let main_thread = std::thread::current().id();
+    let result = setup::panik_builder()
+        .thread_describer(move |id| {
+            if id == main_thread {
+                None
+            } else {
+                Some("pool-worker handling task 4321".to_owned())
+            }
+        })
+        .run_and_handle_panics(|| {
+            let _ = std::thread::spawn(|| panic!("worker")).join();
+            panic!("main")
+        });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 2);
+
+    let worker = panics.iter().find(|p| p.message() == "worker").unwrap();
+    assert_eq!(
+        worker.thread_description(),
+        "pool-worker handling task 4321"
+    );
+
+    let main = panics.iter().find(|p| p.message() == "main").unwrap();
+    assert_eq!(main.thread_description(), main.thread_name());
+}
@@ -0,0 +1,34 @@
+mod setup;
+
+#[test]
+fn thread_id_formatter() {
+    let builder = setup::panik_builder();
+
+    let _ = builder.clone().run_and_handle_panics(|| {
+        std::thread::Builder::new()
+            .name("default".to_owned())
+            .spawn(|| panic!("oh no"))
+            .unwrap()
+            .join()
+    });
+    let panic = &panik::panics()[0];
+    assert_eq!(
+        panic.thread_name(),
+        format!("{:?} (default)", panic.thread_id())
+    );
+
+    let _ = builder
+        .thread_id_formatter(|_| "worker-7".to_owned())
+        .run_and_handle_panics(|| {
+            std::thread::Builder::new()
+                .name("pool".to_owned())
+                .spawn(|| panic!("oh no"))
+                .unwrap()
+                .join()
+        });
+    let panic = &panik::panics()[0];
+    assert_eq!(panic.thread_name(), "worker-7 (pool)");
+    assert!(panic
+        .summary_line()
+        .starts_with("thread pool (worker-7) panicked: oh no"));
+}
@@ -0,0 +1,27 @@
+mod setup;
+
+#[test]
+fn thread_label() {
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        let _ = std::thread::spawn(|| {
+            panik::label_current_thread("pool-worker-3");
+            panic!("oh no")
+        })
+        .join();
+
+        let _ = std::thread::Builder::new()
+            .name("named".to_owned())
+            .spawn(|| {
+                panik::label_current_thread("ignored");
+                panic!("oh no")
+            })
+            .unwrap()
+            .join();
+    });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 2);
+    assert!(panics[0].thread_name().ends_with("(pool-worker-3)"));
+    assert!(panics[1].thread_name().ends_with("(named)"));
+}
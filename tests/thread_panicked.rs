@@ -0,0 +1,27 @@
+mod setup;
+
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn thread_panicked() {
+    let tids = Arc::new(Mutex::new(None));
+    let tids_2 = tids.clone();
+
+    let result = setup::panik_builder().run_and_handle_panics(move || {
+        let dead = std::thread::spawn(|| panic!("oh no"));
+        let dead_tid = dead.thread().id();
+        let _ = dead.join();
+
+        let alive = std::thread::spawn(|| {});
+        let alive_tid = alive.thread().id();
+        let _ = alive.join();
+
+        *tids_2.lock().unwrap() = Some((dead_tid, alive_tid));
+    });
+    assert!(result.is_none());
+
+    let (dead_tid, alive_tid) = tids.lock().unwrap().expect("tids not set");
+    assert!(panik::has_thread_panicked(dead_tid));
+    assert!(!panik::has_thread_panicked(alive_tid));
+    assert!(!panik::has_thread_panicked(std::thread::current().id()));
+}
@@ -0,0 +1,23 @@
+mod setup;
+
+#[test]
+fn thread_stack_size() {
+    const STACK_SIZE: usize = 4 * 1024 * 1024;
+
+    let result = setup::panik_builder().run_and_handle_panics(|| {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(|| panic!("oh no"))
+            .unwrap()
+            .join()
+    });
+    assert!(result.is_none());
+
+    let stack_size = panik::panics()[0].thread_stack_size();
+    if cfg!(target_os = "linux") {
+        // may be rounded up to a page boundary
+        assert!(stack_size.unwrap() >= STACK_SIZE);
+    } else {
+        assert_eq!(stack_size, None);
+    }
+}
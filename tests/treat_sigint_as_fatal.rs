@@ -0,0 +1,29 @@
+#![cfg(all(feature = "use-ctrlc", target_os = "linux"))]
+
+use std::time::{Duration, Instant};
+
+mod setup;
+
+#[test]
+fn treat_sigint_as_fatal() {
+    let result = setup::panik_builder()
+        .treat_sigint_as_fatal(true)
+        .run_and_handle_panics(|| {
+            // safety: raising a signal has no preconditions, and it's handled by ctrlc
+            unsafe {
+                libc::raise(libc::SIGINT);
+            }
+
+            // the main loop notices and exits gracefully
+            let start = Instant::now();
+            while !panik::has_panicked() {
+                assert!(start.elapsed() < Duration::from_secs(5), "not interrupted");
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics.len(), 1);
+    assert_eq!(panics[0].message(), "interrupted");
+}
@@ -0,0 +1,24 @@
+mod setup;
+
+#[test]
+fn unknown_payload_message() {
+    struct Opaque;
+
+    let result = setup::panik_builder()
+        .unknown_payload_message(|info| {
+            let line = info.location().map_or(0, |location| location.line());
+            format!("unknown panic payload at line {}", line)
+        })
+        .run_and_handle_panics(|| {
+            let _ = std::thread::spawn(|| std::panic::panic_any(Opaque)).join();
+            let _ = std::thread::spawn(|| panic!("a string")).join();
+        });
+    assert!(result.is_none());
+
+    let panics = panik::panics();
+    assert_eq!(panics[0].message(), "unknown panic payload at line 13");
+    assert_eq!(panics[0].payload_type_name(), None);
+
+    // recognised payloads are unaffected
+    assert_eq!(panics[1].message(), "a string");
+}
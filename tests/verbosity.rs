@@ -0,0 +1,60 @@
+#![cfg(feature = "use-log")]
+
+use panik::Verbosity;
+use std::sync::Mutex;
+
+static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CapturingLogger;
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        MESSAGES.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn verbosity() {
+    log::set_logger(&CapturingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let run = |verbosity| {
+        MESSAGES.lock().unwrap().clear();
+        let result = panik::Builder::new()
+            .verbosity(verbosity)
+            .run_and_handle_panics(|| std::panic::panic_any("oh no"));
+        assert!(result.is_none());
+        MESSAGES.lock().unwrap().clone()
+    };
+
+    let messages = run(Verbosity::Silent);
+    // once per logging backend, like the panic itself
+    let count = |pred: fn(&String) -> bool| messages.iter().filter(|msg| pred(msg)).count();
+    assert_eq!(
+        count(|msg| msg == "1 threads panicked"),
+        count(|msg| msg.starts_with("handling panic on thread"))
+    );
+    assert!(!messages.iter().any(|msg| msg.contains("panicked: oh no")));
+    assert!(!messages
+        .iter()
+        .any(|msg| msg.starts_with("panic on thread")));
+
+    let messages = run(Verbosity::Summary);
+    let summary = panik::panics()[0].summary_line();
+    assert!(messages.contains(&summary));
+    assert!(!messages
+        .iter()
+        .any(|msg| msg.starts_with("panic on thread")));
+
+    let messages = run(Verbosity::Full);
+    assert!(messages
+        .iter()
+        .any(|msg| msg.starts_with("panic on thread")));
+    assert!(!messages.contains(&panik::panics()[0].summary_line()));
+}
@@ -0,0 +1,19 @@
+mod setup;
+
+#[test]
+fn with_context() {
+    let result = setup::panik_builder().run_and_handle_panics(|| panic!("oh no"));
+    assert!(result.is_none());
+
+    let panic = panik::panics()
+        .remove(0)
+        .with_context("level", "castle")
+        .with_context("player", "1")
+        .with_context("level", "dungeon");
+    assert_eq!(panic.context_map().len(), 2);
+    assert_eq!(panic.context_map()["level"], "dungeon");
+    assert_eq!(panic.context_map()["player"], "1");
+
+    // stored panics are unaffected
+    assert!(panik::panics()[0].context_map().is_empty());
+}